@@ -58,7 +58,7 @@ pub fn sprite_sheet(args: TokenStream, input: TokenStream) -> TokenStream {
 
     let load = quote! {
         impl #name {
-            fn load(
+            pub fn load(
                 asset_server: &Res<AssetServer>,
                 texture_atlases: &mut ResMut<Assets<TextureAtlasLayout>>,
             ) -> Self {
@@ -77,16 +77,21 @@ pub fn sprite_sheet(args: TokenStream, input: TokenStream) -> TokenStream {
                 }
             }
 
-            fn atlas(&self) -> TextureAtlas {
-                let mut rng = thread_rng();
-
+            pub fn atlas(&self, rng: &mut impl Rng) -> TextureAtlas {
                 TextureAtlas {
                     layout: self.layout.clone(),
                     index: rng.gen_range(0..#count),
                 }
             }
 
-            fn texture(&self) -> Handle<Image> {
+            pub fn atlas_with_index(&self, index: usize) -> TextureAtlas {
+                TextureAtlas {
+                    layout: self.layout.clone(),
+                    index,
+                }
+            }
+
+            pub fn texture(&self) -> Handle<Image> {
                 self.texture.clone()
             }
         }
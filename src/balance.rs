@@ -0,0 +1,508 @@
+use crate::entities::status::{StatusKind, StatusOnHit};
+use crate::ron_asset::{ron_diff, RonAssetLoader};
+use crate::world::biome::{Biome, BIOMES};
+use bevy::app::{App, Plugin, PreStartup, Update};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+
+pub struct BalancePlugin;
+
+impl Plugin for BalancePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<GameBalance>()
+            .register_asset_loader(RonAssetLoader::<GameBalance>::new(&["balance.ron"]))
+            .add_systems(PreStartup, load_balance)
+            .add_systems(Update, apply_balance);
+    }
+}
+
+pub static BALANCE_PATH: &str = "balance.ron";
+
+/// Tuning values of the player, spells and world, loaded from `assets/balance.ron`.
+/// Inserted as a resource once the file is loaded and valid, a run can't start before that.
+/// Edits to the file apply while playing, see `apply_balance`.
+#[derive(Asset, Resource, TypePath, Serialize, Deserialize, Clone, Debug)]
+pub struct GameBalance {
+    pub player: PlayerBalance,
+    pub melee: MeleeBalance,
+    pub fireball: FireballBalance,
+    pub frost_bolt: FrostBoltBalance,
+    pub damage: DamageBalance,
+    pub status: StatusBalance,
+    pub mobs: MobBalance,
+    pub spawner: SpawnerBalance,
+    pub unlock: UnlockBalance,
+    pub trees: TreeBalance,
+    /// trees and rocks per open tile, every biome needs an entry
+    pub biomes: HashMap<Biome, BiomeBalance>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PlayerBalance {
+    pub speed: f32,
+    pub radius: f32,
+    pub max_health: f64,
+    /// seconds between two attacks of any kind
+    pub attack_cooldown: f32,
+    /// how quickly the player slows down, also after knockback
+    pub linear_damping: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MeleeBalance {
+    pub damage: f64,
+    pub knockback: f32,
+    /// seconds the swing stays around and hits things
+    pub seconds: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FireballBalance {
+    pub speed: f32,
+    pub radius: f32,
+    /// damage of the explosion, the fireball itself doesn't hurt
+    pub damage: f64,
+    pub knockback: f32,
+    pub explosion_radius: f32,
+    pub explosion_seconds: f32,
+    pub status: StatusOnHit,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FrostBoltBalance {
+    pub speed: f32,
+    pub radius: f32,
+    pub damage: f64,
+    /// seconds until a bolt that didn't hit anything disappears
+    pub lifetime: f32,
+    pub status: StatusOnHit,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DamageBalance {
+    /// seconds an entity can't be hurt again by the same source, unless it has `Invulnerability`
+    pub cool_down_seconds: f32,
+    /// seconds a knocked back entity doesn't steer itself
+    pub hit_stun_seconds: f32,
+}
+
+/// Numbers of the status effects, what each kind does is fixed in `StatusKind::definition`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct StatusBalance {
+    pub burning: BurningBalance,
+    pub chilled: ChilledBalance,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BurningBalance {
+    pub max_stacks: u32,
+    /// seconds between two damage ticks
+    pub tick_seconds: f32,
+    pub damage_per_stack: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ChilledBalance {
+    /// the target freezes once it reaches these
+    pub max_stacks: u32,
+    /// speed multiplier per stack
+    pub slow_per_stack: f32,
+    /// seconds a frozen entity stays frozen
+    pub freeze_seconds: f32,
+}
+
+impl StatusBalance {
+    pub fn max_stacks(&self, kind: StatusKind) -> u32 {
+        match kind {
+            StatusKind::Burning => self.burning.max_stacks,
+            StatusKind::Chilled => self.chilled.max_stacks,
+            StatusKind::Frozen | StatusKind::Stunned => 1,
+        }
+    }
+
+    pub fn slow_per_stack(&self, kind: StatusKind) -> f32 {
+        match kind {
+            StatusKind::Burning => 1.,
+            StatusKind::Chilled => self.chilled.slow_per_stack,
+            StatusKind::Frozen | StatusKind::Stunned => 0.,
+        }
+    }
+
+    /// Seconds between two damage ticks and the damage per stack of each,
+    /// `None` for kinds without damage over time.
+    pub fn damage_over_time(&self, kind: StatusKind) -> Option<(f32, f64)> {
+        match kind {
+            StatusKind::Burning => Some((self.burning.tick_seconds, self.burning.damage_per_stack)),
+            _ => None,
+        }
+    }
+}
+
+/// Shared by every mob kind, the rest is per kind in `mobs.ron`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MobBalance {
+    /// how quickly mobs slow down, also after knockback
+    pub linear_damping: f32,
+    /// seconds until a mob projectile that didn't hit anything disappears
+    pub projectile_lifetime: f32,
+    /// seconds a boss slam stays around and hits things
+    pub slam_seconds: f32,
+}
+
+/// Mobs crawling out of the closed tile frontier, waves spawn on top of these.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SpawnerBalance {
+    pub max_live_mobs: usize,
+    /// mobs never spawn closer to the player than this
+    pub min_distance: f32,
+    /// mobs per second with no open tiles at the start of a run
+    pub base_rate: f32,
+    pub rate_per_open_tile: f32,
+    /// how much the spawn rate grows per minute of the run, 0.5 means +50% each minute
+    pub rate_growth_per_minute: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct UnlockBalance {
+    pub starting_currency: u32,
+    pub mob_kill_reward: u32,
+    pub base_cost: u32,
+    /// added per ring around the start tiles, squared with the number of rings
+    pub distance_cost: u32,
+    /// max distance between the player and the closest point of the tile
+    pub max_player_distance: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TreeBalance {
+    pub health: f64,
+    /// wood handed out per felled tree
+    pub wood: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BiomeBalance {
+    pub trees: Range<usize>,
+    pub rocks: Range<usize>,
+}
+
+#[derive(Debug)]
+pub enum BalanceError {
+    NotPositive { field: &'static str, value: f64 },
+    Negative { field: &'static str, value: f64 },
+    InvalidRange { field: String, range: Range<usize> },
+    MissingBiome(Biome),
+}
+
+impl Display for BalanceError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BalanceError::NotPositive { field, value } => {
+                write!(f, "{field} has to be greater than 0, got {value}")
+            }
+            BalanceError::Negative { field, value } => {
+                write!(f, "{field} can't be negative, got {value}")
+            }
+            BalanceError::InvalidRange { field, range } => {
+                write!(f, "{field} ends before it starts: {range:?}")
+            }
+            BalanceError::MissingBiome(biome) => write!(f, "biomes has no entry for {biome:?}"),
+        }
+    }
+}
+
+impl std::error::Error for BalanceError {}
+
+impl GameBalance {
+    /// Every problem in the file at once, so they can all be fixed in one go.
+    pub fn validate(&self) -> Vec<BalanceError> {
+        let mut errors = vec![];
+
+        let positive = [
+            ("player.speed", self.player.speed as f64),
+            ("player.radius", self.player.radius as f64),
+            ("player.max_health", self.player.max_health),
+            ("melee.seconds", self.melee.seconds as f64),
+            ("fireball.speed", self.fireball.speed as f64),
+            ("fireball.radius", self.fireball.radius as f64),
+            (
+                "fireball.explosion_radius",
+                self.fireball.explosion_radius as f64,
+            ),
+            (
+                "fireball.explosion_seconds",
+                self.fireball.explosion_seconds as f64,
+            ),
+            ("frost_bolt.speed", self.frost_bolt.speed as f64),
+            ("frost_bolt.radius", self.frost_bolt.radius as f64),
+            ("frost_bolt.lifetime", self.frost_bolt.lifetime as f64),
+            (
+                "status.burning.max_stacks",
+                self.status.burning.max_stacks as f64,
+            ),
+            (
+                "status.burning.tick_seconds",
+                self.status.burning.tick_seconds as f64,
+            ),
+            (
+                "status.chilled.max_stacks",
+                self.status.chilled.max_stacks as f64,
+            ),
+            (
+                "mobs.projectile_lifetime",
+                self.mobs.projectile_lifetime as f64,
+            ),
+            ("mobs.slam_seconds", self.mobs.slam_seconds as f64),
+            (
+                "unlock.max_player_distance",
+                self.unlock.max_player_distance as f64,
+            ),
+            ("trees.health", self.trees.health),
+        ];
+
+        let not_negative = [
+            ("player.attack_cooldown", self.player.attack_cooldown as f64),
+            ("player.linear_damping", self.player.linear_damping as f64),
+            ("melee.damage", self.melee.damage),
+            ("melee.knockback", self.melee.knockback as f64),
+            ("fireball.damage", self.fireball.damage),
+            ("fireball.knockback", self.fireball.knockback as f64),
+            (
+                "fireball.status.seconds",
+                self.fireball.status.seconds as f64,
+            ),
+            ("frost_bolt.damage", self.frost_bolt.damage),
+            (
+                "frost_bolt.status.seconds",
+                self.frost_bolt.status.seconds as f64,
+            ),
+            (
+                "damage.cool_down_seconds",
+                self.damage.cool_down_seconds as f64,
+            ),
+            (
+                "damage.hit_stun_seconds",
+                self.damage.hit_stun_seconds as f64,
+            ),
+            (
+                "status.burning.damage_per_stack",
+                self.status.burning.damage_per_stack,
+            ),
+            (
+                "status.chilled.slow_per_stack",
+                self.status.chilled.slow_per_stack as f64,
+            ),
+            (
+                "status.chilled.freeze_seconds",
+                self.status.chilled.freeze_seconds as f64,
+            ),
+            ("mobs.linear_damping", self.mobs.linear_damping as f64),
+            ("spawner.min_distance", self.spawner.min_distance as f64),
+            ("spawner.base_rate", self.spawner.base_rate as f64),
+            (
+                "spawner.rate_per_open_tile",
+                self.spawner.rate_per_open_tile as f64,
+            ),
+            (
+                "spawner.rate_growth_per_minute",
+                self.spawner.rate_growth_per_minute as f64,
+            ),
+        ];
+
+        for (field, value) in positive {
+            if value.is_nan() || value <= 0. {
+                errors.push(BalanceError::NotPositive { field, value });
+            }
+        }
+
+        for (field, value) in not_negative {
+            if value.is_nan() || value < 0. {
+                errors.push(BalanceError::Negative { field, value });
+            }
+        }
+
+        for biome in BIOMES {
+            let Some(balance) = self.biomes.get(&biome) else {
+                errors.push(BalanceError::MissingBiome(biome));
+                continue;
+            };
+
+            for (name, range) in [("trees", &balance.trees), ("rocks", &balance.rocks)] {
+                if range.start > range.end {
+                    errors.push(BalanceError::InvalidRange {
+                        field: format!("biomes.{biome:?}.{name}"),
+                        range: range.clone(),
+                    });
+                }
+            }
+        }
+
+        errors
+    }
+
+    /// Only call on a validated balance, those have an entry for every biome.
+    pub fn biome(&self, biome: Biome) -> &BiomeBalance {
+        &self.biomes[&biome]
+    }
+}
+
+#[derive(Resource)]
+pub struct GameBalanceHandle(pub Handle<GameBalance>);
+
+fn load_balance(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(GameBalanceHandle(asset_server.load(BALANCE_PATH)));
+}
+
+/// Copies the loaded balance into the `GameBalance` resource, unless it has errors.
+/// Also runs when the file is hot reloaded, an invalid edit keeps the previous balance.
+fn apply_balance(
+    mut commands: Commands,
+    mut asset_events: EventReader<AssetEvent<GameBalance>>,
+    handle: Res<GameBalanceHandle>,
+    balances: Res<Assets<GameBalance>>,
+    current: Option<Res<GameBalance>>,
+) {
+    for event in asset_events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event
+        else {
+            continue;
+        };
+
+        if *id != handle.0.id() {
+            continue;
+        }
+
+        let Some(balance) = balances.get(*id) else {
+            continue;
+        };
+
+        let errors = balance.validate();
+
+        if !errors.is_empty() {
+            for error in errors {
+                error!("invalid {BALANCE_PATH}: {error}");
+            }
+
+            continue;
+        }
+
+        match &current {
+            None => info!("loaded the game balance from {BALANCE_PATH}"),
+            Some(current) => {
+                let changes = ron_diff(&**current, balance);
+
+                // a reload can report both events for the same change
+                if changes.is_empty() {
+                    continue;
+                }
+
+                info!("reloaded {BALANCE_PATH}: {}", changes.join(", "));
+            }
+        }
+
+        commands.insert_resource(balance.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn balance() -> GameBalance {
+        ron::from_str(include_str!("../assets/balance.ron")).unwrap()
+    }
+
+    #[test]
+    fn shipped_balance_is_valid() {
+        let errors = balance().validate();
+
+        assert!(errors.is_empty(), "{errors:?}");
+    }
+
+    #[test]
+    fn reports_every_bad_number() {
+        let mut balance = balance();
+        balance.player.speed = 0.;
+        balance.fireball.radius = f32::NAN;
+        balance.melee.damage = -1.;
+
+        let errors = balance.validate();
+
+        assert_eq!(errors.len(), 3);
+        assert!(errors.iter().any(|error| matches!(
+            error,
+            BalanceError::NotPositive {
+                field: "player.speed",
+                ..
+            }
+        )));
+        assert!(errors.iter().any(|error| matches!(
+            error,
+            BalanceError::NotPositive {
+                field: "fireball.radius",
+                ..
+            }
+        )));
+        assert!(errors.iter().any(|error| matches!(
+            error,
+            BalanceError::Negative {
+                field: "melee.damage",
+                ..
+            }
+        )));
+    }
+
+    #[test]
+    fn reports_bad_status_numbers() {
+        let mut balance = balance();
+        balance.status.burning.tick_seconds = 0.;
+        balance.status.chilled.max_stacks = 0;
+        balance.status.chilled.slow_per_stack = -0.2;
+
+        let errors = balance.validate();
+
+        assert_eq!(errors.len(), 3, "{errors:?}");
+        assert!(errors.iter().any(|error| matches!(
+            error,
+            BalanceError::NotPositive {
+                field: "status.burning.tick_seconds",
+                ..
+            }
+        )));
+        assert!(errors.iter().any(|error| matches!(
+            error,
+            BalanceError::NotPositive {
+                field: "status.chilled.max_stacks",
+                ..
+            }
+        )));
+        assert!(errors.iter().any(|error| matches!(
+            error,
+            BalanceError::Negative {
+                field: "status.chilled.slow_per_stack",
+                ..
+            }
+        )));
+    }
+
+    #[test]
+    fn reports_missing_biomes_and_inverted_ranges() {
+        let mut balance = balance();
+        balance.biomes.remove(&Biome::Forest);
+        balance.biomes.get_mut(&Biome::Clearing).unwrap().trees = Range { start: 5, end: 2 };
+
+        let errors = balance.validate();
+
+        assert_eq!(errors.len(), 2);
+        assert!(errors
+            .iter()
+            .any(|error| matches!(error, BalanceError::MissingBiome(Biome::Forest))));
+        assert!(errors.iter().any(|error| matches!(
+            error,
+            BalanceError::InvalidRange { field, .. } if field == "biomes.Clearing.trees"
+        )));
+    }
+}
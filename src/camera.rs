@@ -1,4 +1,5 @@
 use crate::entities::data::Player;
+use crate::AppState;
 use bevy::app::App;
 use bevy::prelude::*;
 use bevy_xpbd_2d::components::LinearVelocity;
@@ -9,7 +10,11 @@ pub struct CameraPlugin;
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, setup)
-            .add_systems(FixedUpdate, follow_player);
+            .add_systems(
+                FixedUpdate,
+                follow_player.run_if(in_state(AppState::Playing)),
+            )
+            .add_systems(OnExit(AppState::Playing), stop_camera);
     }
 }
 
@@ -50,3 +55,10 @@ pub fn follow_player(
     camera_velocity.x = change.x;
     camera_velocity.y = change.y;
 }
+
+/// `follow_player` only runs while playing, so the camera would keep drifting otherwise.
+fn stop_camera(mut camera_query: Query<&mut LinearVelocity, With<Camera>>) {
+    for mut camera_velocity in camera_query.iter_mut() {
+        camera_velocity.0 = Vec2::ZERO;
+    }
+}
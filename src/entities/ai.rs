@@ -0,0 +1,211 @@
+use crate::entities::data::{Dead, Mob, Player};
+use crate::entities::mob_kind::MobStats;
+use crate::entities::status::{blocks_actions, StatusEffects};
+use crate::AppState;
+use bevy::app::{App, Plugin, Update};
+use bevy::prelude::*;
+use rand::{thread_rng, Rng};
+use std::f32::consts::TAU;
+
+pub struct AiPlugin;
+
+impl Plugin for AiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<MobStateChanged>().add_systems(
+            Update,
+            (update_mob_ai, log_mob_state_changes)
+                .chain()
+                .run_if(in_state(AppState::Playing)),
+        );
+    }
+}
+
+/// Idle mobs wander around within this distance of their home.
+pub static WANDER_RADIUS: f32 = 48.;
+/// Fraction of the mob speed used while wandering or walking home.
+pub static WANDER_SPEED_FACTOR: f32 = 0.4;
+/// Seconds an idle mob stands still before it picks a new wander target.
+pub static IDLE_SECONDS: std::ops::Range<f32> = 1.5..4.;
+/// Seconds a mob stares at the player before it starts to chase.
+pub static AGGRO_SECONDS: f32 = 0.3;
+/// Seconds the player has to stay out of the give up radius before a chase is abandoned.
+pub static LOSE_PLAYER_SECONDS: f32 = 3.;
+pub static LUNGE_SECONDS: f32 = 0.2;
+/// Speed multiplier of the lunge following an attack wind-up.
+pub static LUNGE_SPEED_FACTOR: f32 = 2.5;
+/// Tint of a mob winding up an attack, so the player can see it coming.
+pub static WIND_UP_COLOR: Color = Color::rgb(1., 0.3, 0.3);
+/// Mobs count as arrived once they are this close to their wander target or home.
+static ARRIVE_DISTANCE: f32 = 4.;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum MobState {
+    /// standing around
+    Idle,
+    /// walking to a random point near home
+    Wander,
+    /// noticed the player, chases once the reaction time is over
+    Aggro,
+    Chase,
+    /// standing still before lunging at the player
+    WindUp,
+    /// lunging at the player, ranged mobs fire when entering this state
+    Attack,
+    /// lost the player and walks back home
+    GiveUp,
+}
+
+#[derive(Component)]
+pub struct MobAi {
+    pub state: MobState,
+    /// duration of the current state, in `Chase` how long the player has been lost
+    pub timer: Timer,
+    /// where the mob spawned, it wanders around and returns to it
+    pub home: Vec2,
+    pub wander_target: Vec2,
+    /// direction of the lunge or shot, fixed when the wind-up ends
+    pub attack_direction: Vec2,
+    /// time until the mob may wind up its next attack
+    pub attack_cooldown: Timer,
+    pub detection_radius: f32,
+    pub give_up_radius: f32,
+    pub attack_range: f32,
+    pub wind_up: f32,
+}
+
+impl MobAi {
+    pub fn new(stats: &MobStats, home: Vec2) -> Self {
+        MobAi {
+            state: MobState::Idle,
+            timer: Timer::from_seconds(IDLE_SECONDS.start, TimerMode::Once),
+            home,
+            wander_target: home,
+            attack_direction: Vec2::ZERO,
+            attack_cooldown: Timer::from_seconds(stats.attack_interval, TimerMode::Once),
+            detection_radius: stats.detection_radius,
+            give_up_radius: stats.give_up_radius,
+            attack_range: stats.attack_range,
+            wind_up: stats.wind_up,
+        }
+    }
+
+    /// Starts out chasing the player instead of idling, e.g. for wave mobs.
+    pub fn chasing(mut self) -> Self {
+        self.state = MobState::Chase;
+        self.timer = Timer::from_seconds(LOSE_PLAYER_SECONDS, TimerMode::Once);
+        self
+    }
+
+    fn transition(&mut self, state: MobState, seconds: f32) {
+        self.state = state;
+        self.timer = Timer::from_seconds(seconds, TimerMode::Once);
+    }
+}
+
+/// Sent whenever a mob switches its AI state.
+#[derive(Event, Debug)]
+pub struct MobStateChanged {
+    pub entity: Entity,
+    pub from: MobState,
+    pub to: MobState,
+}
+
+pub fn update_mob_ai(
+    time: Res<Time>,
+    mut mob_query: Query<(Entity, &mut MobAi, &Transform, Option<&StatusEffects>), With<Mob>>,
+    // mobs lose track of a dead player
+    player_query: Query<&Transform, (With<Player>, Without<Mob>, Without<Dead>)>,
+    mut state_changed: EventWriter<MobStateChanged>,
+) {
+    let player_position = player_query
+        .get_single()
+        .ok()
+        .map(|transform| transform.translation.xy());
+    let mut rng = thread_rng();
+
+    for (entity, mut ai, transform, status) in mob_query.iter_mut() {
+        // frozen and stunned mobs keep their state until it wears off
+        if blocks_actions(status) {
+            continue;
+        }
+
+        let position = transform.translation.xy();
+        let player_distance =
+            player_position.map_or(f32::INFINITY, |player| player.distance(position));
+        let from = ai.state;
+
+        ai.timer.tick(time.delta());
+        ai.attack_cooldown.tick(time.delta());
+
+        match ai.state {
+            MobState::Idle | MobState::Wander | MobState::GiveUp
+                if player_distance <= ai.detection_radius =>
+            {
+                ai.transition(MobState::Aggro, AGGRO_SECONDS);
+            }
+            MobState::Idle => {
+                if ai.timer.finished() {
+                    let angle = rng.gen_range(0. ..TAU);
+                    let distance = rng.gen_range(0. ..WANDER_RADIUS);
+
+                    ai.wander_target = ai.home + Vec2::from_angle(angle) * distance;
+                    ai.transition(MobState::Wander, 0.);
+                }
+            }
+            MobState::Wander => {
+                if position.distance(ai.wander_target) <= ARRIVE_DISTANCE {
+                    ai.transition(MobState::Idle, rng.gen_range(IDLE_SECONDS.clone()));
+                }
+            }
+            MobState::GiveUp => {
+                if position.distance(ai.home) <= ARRIVE_DISTANCE {
+                    ai.transition(MobState::Idle, rng.gen_range(IDLE_SECONDS.clone()));
+                }
+            }
+            MobState::Aggro => {
+                if ai.timer.finished() {
+                    ai.transition(MobState::Chase, LOSE_PLAYER_SECONDS);
+                }
+            }
+            MobState::Chase => {
+                if player_distance <= ai.give_up_radius {
+                    ai.timer.reset();
+                }
+
+                if ai.timer.finished() {
+                    ai.transition(MobState::GiveUp, 0.);
+                } else if player_distance <= ai.attack_range && ai.attack_cooldown.finished() {
+                    let wind_up = ai.wind_up;
+                    ai.transition(MobState::WindUp, wind_up);
+                }
+            }
+            MobState::WindUp => {
+                if ai.timer.finished() {
+                    ai.attack_direction = player_position
+                        .map_or(Vec2::ZERO, |player| (player - position).normalize_or_zero());
+                    ai.attack_cooldown.reset();
+                    ai.transition(MobState::Attack, LUNGE_SECONDS);
+                }
+            }
+            MobState::Attack => {
+                if ai.timer.finished() {
+                    ai.transition(MobState::Chase, LOSE_PLAYER_SECONDS);
+                }
+            }
+        }
+
+        if ai.state != from {
+            state_changed.send(MobStateChanged {
+                entity,
+                from,
+                to: ai.state,
+            });
+        }
+    }
+}
+
+fn log_mob_state_changes(mut state_changed: EventReader<MobStateChanged>) {
+    for event in state_changed.read() {
+        debug!("{:?}: {:?} -> {:?}", event.entity, event.from, event.to);
+    }
+}
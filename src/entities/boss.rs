@@ -0,0 +1,432 @@
+use crate::balance::GameBalance;
+use crate::entities::ai::{MobAi, MobState};
+use crate::entities::data::{
+    Attacker, Damage, DamageType, Dead, DespawnTimer, EntityType, Health, HitStun, Player,
+};
+use crate::entities::entity::deal_damage;
+use crate::entities::event::EntityDeathEvent;
+use crate::entities::longtime_attack::LongTimeAttack;
+use crate::entities::mob::{move_mob, spawn_mob};
+use crate::entities::mob_kind::{MobKind, MobKinds, MobRegistry, MobStats};
+use crate::entities::status::{blocks_actions, StatusEffects};
+use crate::ui::{AttackType, InventorySlot};
+use crate::world::grid::{TileGrid, TileState};
+use crate::world::lair::lair_boss;
+use crate::world::seed::WorldSeed;
+use crate::{AppState, PhysicsLayers};
+use bevy::app::{App, Plugin, Update};
+use bevy::prelude::*;
+use bevy_xpbd_2d::prelude::*;
+use iter_tools::Itertools;
+use rand::{thread_rng, Rng};
+use serde::{Deserialize, Serialize};
+use std::f32::consts::TAU;
+
+pub struct BossPlugin;
+
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<BossPhaseChanged>()
+            .add_event::<BossDefeated>()
+            .add_systems(
+                Update,
+                (
+                    (
+                        defeat_bosses.after(deal_damage),
+                        // lairs only change with the grid, or get a boss once the registry loads
+                        spawn_lair_bosses.run_if(
+                            resource_changed::<TileGrid>
+                                .or_else(resource_exists_and_changed::<MobRegistry>),
+                        ),
+                    )
+                        .chain(),
+                    run_boss_patterns.after(move_mob),
+                    grant_boss_rewards.after(defeat_bosses),
+                )
+                    .run_if(in_state(AppState::Playing)),
+            );
+    }
+}
+
+/// Seconds a boss waits after a phase change before it attacks again.
+pub static PHASE_CHANGE_PAUSE: f32 = 1.;
+/// Summoned adds appear this far outside of the boss.
+pub static SUMMON_DISTANCE: f32 = 24.;
+
+/// Phases and attacks of a boss kind, part of its entry in `assets/mobs.ron`.
+#[derive(Component, Debug, Clone, Serialize, Deserialize)]
+pub struct BossStats {
+    /// ordered by descending `health_threshold`, the first one starts at full health
+    pub phases: Vec<BossPhase>,
+    /// attack handed to the player for beating the boss
+    pub reward: Option<AttackType>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BossPhase {
+    /// the phase starts once health drops to this fraction of the maximum
+    pub health_threshold: f64,
+    /// seconds between two attacks
+    pub attack_interval: f32,
+    /// seconds the boss telegraphs an attack before doing it
+    pub telegraph: f32,
+    /// used in order, starting over after the last one
+    pub attacks: Vec<BossAttack>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum BossAttack {
+    /// dash in a straight line at the player
+    Charge { speed: f32, seconds: f32 },
+    /// damage everything around the boss
+    Slam {
+        radius: f32,
+        damage: f64,
+        #[serde(default)]
+        damage_type: DamageType,
+    },
+    /// call in mobs around the boss
+    Summon { kind: MobKind, count: u32 },
+}
+
+pub enum BossAction {
+    Cooldown(Timer),
+    Telegraph { attack: BossAttack, timer: Timer },
+    Charge { velocity: Vec2, timer: Timer },
+}
+
+#[derive(Component)]
+pub struct Boss {
+    /// tile the boss guards
+    pub lair: IVec2,
+    pub max_health: f64,
+    pub phase: usize,
+    pub next_attack: usize,
+    pub action: BossAction,
+}
+
+impl Boss {
+    pub fn new(lair: IVec2, max_health: f64, stats: &BossStats) -> Self {
+        let first_interval = stats
+            .phases
+            .first()
+            .map_or(0., |phase| phase.attack_interval);
+
+        Boss {
+            lair,
+            max_health,
+            phase: 0,
+            next_attack: 0,
+            action: BossAction::Cooldown(Timer::from_seconds(first_interval, TimerMode::Once)),
+        }
+    }
+
+    pub fn is_telegraphing(&self) -> bool {
+        matches!(self.action, BossAction::Telegraph { .. })
+    }
+}
+
+#[derive(Event)]
+pub struct BossPhaseChanged {
+    pub boss: Entity,
+    pub phase: usize,
+}
+
+#[derive(Event)]
+pub struct BossDefeated {
+    pub kind: MobKind,
+    pub lair: IVec2,
+    pub reward: Option<AttackType>,
+    /// who landed the killing blow, only the player earns the reward
+    pub attacker: Option<Entity>,
+}
+
+/// Spawns the boss of every open lair tile that has not been beaten and has no living boss.
+fn spawn_lair_bosses(
+    mut commands: Commands,
+    grid: Res<TileGrid>,
+    seed: Res<WorldSeed>,
+    mob_kinds: MobKinds,
+    bosses: Query<&Boss>,
+    balance: Res<GameBalance>,
+) {
+    if !mob_kinds.is_loaded() {
+        return;
+    }
+
+    for (position, tile) in grid.iter() {
+        if tile.state != TileState::Open || tile.changes.boss_defeated {
+            continue;
+        }
+
+        let Some(kind) = lair_boss(&seed, position) else {
+            continue;
+        };
+
+        if bosses.iter().any(|boss| boss.lair == position) {
+            continue;
+        }
+
+        let Some(stats) = mob_kinds.stats(kind) else {
+            continue;
+        };
+
+        let Some(boss_stats) = &stats.boss else {
+            warn!("{kind:?} guards a lair but has no boss stats");
+            continue;
+        };
+
+        spawn_mob(
+            &mut commands,
+            kind,
+            stats,
+            TileGrid::grid_to_world(position),
+            &balance,
+        )
+        .insert((
+            Boss::new(position, stats.health, boss_stats),
+            boss_stats.clone(),
+        ));
+
+        info!("{kind:?} appeared in the lair at {position}");
+    }
+}
+
+fn run_boss_patterns(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut bosses: Query<
+        (
+            Entity,
+            &mut Boss,
+            &BossStats,
+            &MobAi,
+            &Health,
+            &Transform,
+            &mut LinearVelocity,
+            Option<&StatusEffects>,
+        ),
+        Without<HitStun>,
+    >,
+    player_query: Query<&Transform, (With<Player>, Without<Boss>, Without<Dead>)>,
+    mob_kinds: MobKinds,
+    mut phase_changed: EventWriter<BossPhaseChanged>,
+    balance: Res<GameBalance>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+
+    let player_position = player_transform.translation.xy();
+
+    for (entity, mut boss, stats, ai, health, transform, mut linear_velocity, status) in
+        bosses.iter_mut()
+    {
+        let position = transform.translation.xy();
+        let health_fraction = health.0 / boss.max_health;
+
+        while stats
+            .phases
+            .get(boss.phase + 1)
+            .is_some_and(|phase| health_fraction <= phase.health_threshold)
+        {
+            boss.phase += 1;
+            boss.next_attack = 0;
+            boss.action =
+                BossAction::Cooldown(Timer::from_seconds(PHASE_CHANGE_PAUSE, TimerMode::Once));
+
+            info!("boss entered phase {}", boss.phase + 1);
+            phase_changed.send(BossPhaseChanged {
+                boss: entity,
+                phase: boss.phase,
+            });
+        }
+
+        let Some(phase) = stats.phases.get(boss.phase) else {
+            continue;
+        };
+
+        // bosses only attack while they are fighting the player
+        if ai.state != MobState::Chase || blocks_actions(status) {
+            continue;
+        }
+
+        match &mut boss.action {
+            BossAction::Cooldown(timer) => {
+                if !timer.tick(time.delta()).finished() || phase.attacks.is_empty() {
+                    continue;
+                }
+
+                let attack = phase.attacks[boss.next_attack % phase.attacks.len()].clone();
+
+                boss.next_attack += 1;
+                boss.action = BossAction::Telegraph {
+                    attack,
+                    timer: Timer::from_seconds(phase.telegraph, TimerMode::Once),
+                };
+            }
+            BossAction::Telegraph { attack, timer } => {
+                linear_velocity.0 = Vec2::ZERO;
+
+                if !timer.tick(time.delta()).finished() {
+                    continue;
+                }
+
+                let attack = attack.clone();
+                let cooldown = Timer::from_seconds(phase.attack_interval, TimerMode::Once);
+
+                boss.action = match attack {
+                    BossAttack::Charge { speed, seconds } => BossAction::Charge {
+                        velocity: (player_position - position).normalize_or_zero() * speed,
+                        timer: Timer::from_seconds(seconds, TimerMode::Once),
+                    },
+                    BossAttack::Slam {
+                        radius,
+                        damage,
+                        damage_type,
+                    } => {
+                        spawn_slam(
+                            &mut commands,
+                            entity,
+                            position,
+                            radius,
+                            Damage(damage, damage_type),
+                            balance.mobs.slam_seconds,
+                        );
+                        BossAction::Cooldown(cooldown)
+                    }
+                    BossAttack::Summon { kind, count } => {
+                        if let Some(stats) = mob_kinds.stats(kind) {
+                            summon_adds(&mut commands, kind, stats, position, count, &balance);
+                        }
+
+                        BossAction::Cooldown(cooldown)
+                    }
+                };
+            }
+            BossAction::Charge { velocity, timer } => {
+                linear_velocity.0 = *velocity;
+
+                if timer.tick(time.delta()).finished() {
+                    boss.action = BossAction::Cooldown(Timer::from_seconds(
+                        phase.attack_interval,
+                        TimerMode::Once,
+                    ));
+                }
+            }
+        }
+    }
+}
+
+fn spawn_slam(
+    commands: &mut Commands,
+    boss: Entity,
+    position: Vec2,
+    radius: f32,
+    damage: Damage,
+    seconds: f32,
+) {
+    commands.spawn((
+        LongTimeAttack {
+            damaged_entities: vec![],
+            from: EntityType::Mob,
+        },
+        damage,
+        Attacker(boss),
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(0.85, 0.95, 1., 0.5),
+                custom_size: Some(Vec2::splat(radius * 2.)),
+                ..default()
+            },
+            transform: Transform::from_translation(position.extend(-1.)),
+            ..default()
+        },
+        DespawnTimer::from_seconds(seconds),
+        Sensor,
+        CollisionLayers::new([PhysicsLayers::Mob], [PhysicsLayers::Player]),
+        Collider::circle(radius),
+        RigidBody::Static,
+    ));
+}
+
+fn summon_adds(
+    commands: &mut Commands,
+    kind: MobKind,
+    stats: &MobStats,
+    position: Vec2,
+    count: u32,
+    balance: &GameBalance,
+) {
+    let mut rng = thread_rng();
+
+    for _ in 0..count {
+        let offset = Vec2::from_angle(rng.gen_range(0. ..TAU)) * (stats.radius + SUMMON_DISTANCE);
+
+        spawn_mob(commands, kind, stats, position + offset, balance)
+            .insert(MobAi::new(stats, position + offset).chasing());
+    }
+}
+
+/// Remembers beaten bosses on their lair tile so they don't come back.
+fn defeat_bosses(
+    mut death_events: EventReader<EntityDeathEvent>,
+    bosses: Query<(&Boss, &MobKind, &BossStats)>,
+    mut grid: ResMut<TileGrid>,
+    mut boss_defeated: EventWriter<BossDefeated>,
+) {
+    for event in death_events.read().unique_by(|event| event.entity) {
+        let Ok((boss, kind, stats)) = bosses.get(event.entity) else {
+            continue;
+        };
+
+        if let Some(tile) = grid.get_mut(boss.lair) {
+            tile.changes.boss_defeated = true;
+        }
+
+        info!("{kind:?} was defeated");
+        boss_defeated.send(BossDefeated {
+            kind: *kind,
+            lair: boss.lair,
+            reward: stats.reward,
+            attacker: event.attacker,
+        });
+    }
+}
+
+/// Puts the reward attack into the first free inventory slot, unless the player already has it.
+fn grant_boss_rewards(
+    mut boss_defeated: EventReader<BossDefeated>,
+    mut inventory: Query<&mut InventorySlot>,
+    players: Query<(), With<Player>>,
+) {
+    for event in boss_defeated.read() {
+        let Some(reward) = event.reward else {
+            continue;
+        };
+
+        if !event
+            .attacker
+            .is_some_and(|attacker| players.contains(attacker))
+        {
+            continue;
+        }
+
+        if inventory.iter().any(|slot| slot.attack == Some(reward)) {
+            continue;
+        }
+
+        let free_slot = inventory
+            .iter_mut()
+            .filter(|slot| slot.attack.is_none())
+            .min_by_key(|slot| slot.index);
+
+        match free_slot {
+            Some(mut slot) => {
+                slot.attack = Some(reward);
+                info!("unlocked {reward:?} in slot {}", slot.index + 1);
+            }
+            None => warn!("no free inventory slot for {reward:?}"),
+        }
+    }
+}
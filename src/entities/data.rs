@@ -1,23 +1,53 @@
-use bevy::prelude::{Component, Timer, TimerMode};
+use bevy::prelude::{Color, Component, Entity, Timer, TimerMode};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum DamageType {
+    #[default]
+    Physical,
+    Fire,
+    Frost,
+}
 
-pub static PLAYER_ATTACK_COOLDOWN: f32 = 0.5;
+#[derive(Component)]
+pub struct Damage(pub f64, pub DamageType);
 
-pub static MOB_SPEED: f32 = 100.;
-pub static MOB_RADIUS: f32 = 4.;
-pub static MOB_HEALTH: f32 = 10.;
-pub static MOB_DAMAGE: f32 = 4.;
+/// Entity credited with the damage this entity deals, e.g. the player for their fireballs.
+/// Entities without it are credited themselves.
+#[derive(Component)]
+pub struct Attacker(pub Entity);
 
-pub static PLAYER_SPEED: f32 = 175.;
-pub static PLAYER_RADIUS: f32 = 8.;
-pub static MAX_PLAYER_HEALTH: f64 = 30.;
-pub static PLAYER_DAMAGE: f64 = 5.;
+/// Multiplier on incoming damage per type, 0 makes immune and above 1 is a weakness.
+/// Types without an entry take full damage.
+#[derive(Component, Default, Clone, Debug)]
+pub struct Resistances(pub HashMap<DamageType, f64>);
 
-pub static FIRE_BALL_RADIUS: f32 = 6.;
-pub static FIRE_BALL_SPEED: f32 = 300.;
-pub static FIRE_BALL_DAMAGE: f32 = 4.;
+impl Resistances {
+    pub fn multiplier(&self, damage_type: DamageType) -> f64 {
+        self.0.get(&damage_type).copied().unwrap_or(1.)
+    }
+}
+
+/// Where a knockback pushes away from.
+#[derive(Clone, Copy, Debug)]
+pub enum KnockbackOrigin {
+    /// away from the `Attacker`, e.g. the player swinging a sword
+    Attacker,
+    /// away from the centre of the attack, e.g. an explosion
+    Center,
+}
 
+/// Velocity added to everything this attack damages.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Knockback {
+    pub strength: f32,
+    pub origin: KnockbackOrigin,
+}
+
+/// Fraction of incoming knockback that is ignored, 1 can't be pushed at all.
 #[derive(Component)]
-pub struct Damage(pub f64);
+pub struct KnockbackResistance(pub f32);
 
 #[derive(Component)]
 pub struct Health(pub f64);
@@ -25,18 +55,35 @@ pub struct Health(pub f64);
 #[derive(Component)]
 pub struct Player;
 
+/// The player ran out of health, they are kept around until they respawn.
+#[derive(Component)]
+pub struct Dead;
+
 #[derive(Component)]
 pub struct Mob;
 
+#[derive(Component)]
+pub struct MovementSpeed(pub f32);
+
+/// Sprite colour to return to once a damage flash is over.
+#[derive(Component)]
+pub struct BaseColor(pub Color);
+
 #[derive(Component)]
 pub struct Fireball();
 
+/// Flies until it collides with something and is removed on the first hit.
+#[derive(Component)]
+pub struct Projectile;
+
 #[derive(PartialEq, Component, Debug)]
 pub enum EntityType {
     Player,
     Mob,
     Wall,
     Spell,
+    Tree,
+    EnemyProjectile,
 }
 
 #[derive(Component)]
@@ -63,18 +110,38 @@ impl AttackTimer {
 #[derive(Component)]
 pub struct PlayerAttackCoolDown(pub Timer);
 
-impl Default for PlayerAttackCoolDown {
-    fn default() -> PlayerAttackCoolDown {
-        PlayerAttackCoolDown(Timer::from_seconds(PLAYER_ATTACK_COOLDOWN, TimerMode::Once))
+impl PlayerAttackCoolDown {
+    pub fn new(seconds: f32) -> PlayerAttackCoolDown {
+        PlayerAttackCoolDown(Timer::from_seconds(seconds, TimerMode::Once))
     }
 }
 
+/// Seconds the entities hurt by this source can't be hurt by it again.
 #[derive(Component)]
-pub struct DamageCoolDown(pub Timer);
+pub struct Invulnerability(pub f32);
+
+/// Sources that recently hurt an entity, hits from any other source still land.
+#[derive(Component, Default)]
+pub struct DamageCoolDown(pub HashMap<Entity, Timer>);
 
 impl DamageCoolDown {
-    pub fn new(seconds: f32) -> DamageCoolDown {
-        DamageCoolDown(Timer::from_seconds(seconds, TimerMode::Once))
+    pub fn blocks(&self, source: Entity) -> bool {
+        self.0.get(&source).is_some_and(|timer| !timer.finished())
+    }
+
+    pub fn add(&mut self, source: Entity, seconds: f32) {
+        self.0
+            .insert(source, Timer::from_seconds(seconds, TimerMode::Once));
+    }
+}
+
+/// Set after a knockback, mobs don't move on their own while it lasts.
+#[derive(Component)]
+pub struct HitStun(pub Timer);
+
+impl HitStun {
+    pub fn new(seconds: f32) -> HitStun {
+        HitStun(Timer::from_seconds(seconds, TimerMode::Once))
     }
 }
 
@@ -1,12 +1,20 @@
+use crate::balance::GameBalance;
+use crate::entities::ai::{MobAi, MobState, WIND_UP_COLOR};
+use crate::entities::boss::Boss;
 use crate::entities::data::{
-    AttackTimer, AttackableFrom, Damage, DamageCoolDown, DespawnTimer, EntityType, Health, Mob,
-    Player,
+    AttackTimer, AttackableFrom, BaseColor, Damage, DamageCoolDown, DespawnTimer, EntityType,
+    Health, HitStun, Invulnerability, KnockbackResistance, Mob, Player, Projectile, Resistances,
 };
-use crate::entities::event::{EntityDamageEvent, EntityDeathEvent};
+use crate::entities::event::{EntityDamageEvent, EntityDeathEvent, KnockbackEvent};
+use crate::entities::longtime_attack::LongTimeAttack;
+use crate::entities::status::{blocks_actions, ApplyStatusEvent, StatusEffects};
+use crate::AppState;
 use bevy::app::{App, Plugin, PostUpdate, Update};
 use bevy::prelude::{
-    Color, Commands, Entity, EventReader, EventWriter, Or, Query, Res, Sprite, Time, With, Without,
+    debug, in_state, Color, Commands, DespawnRecursiveExt, Entity, EventReader, EventWriter,
+    IntoSystemConfigs, Local, OnEnter, Or, Query, Res, Sprite, Time, With, Without,
 };
+use bevy::utils::HashMap;
 use bevy_xpbd_2d::prelude::*;
 use iter_tools::Itertools;
 
@@ -14,17 +22,24 @@ pub struct EntityPlugin;
 
 impl Plugin for EntityPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, (deal_damage_on_collision, deal_damage))
-            .add_systems(PostUpdate, remove_dead_entities)
-            .add_systems(
-                Update,
-                (
-                    tick_damage_cool_down,
-                    remove_damage_cool_down,
-                    color_mob_on_damage,
-                    despawn,
-                ),
-            );
+        app.add_systems(
+            Update,
+            (deal_damage_on_collision, deal_damage).run_if(in_state(AppState::Playing)),
+        )
+        .add_systems(PostUpdate, remove_dead_entities)
+        .add_systems(OnEnter(AppState::Menu), despawn_run_entities)
+        .add_systems(
+            Update,
+            (
+                tick_damage_cool_down,
+                remove_damage_cool_down,
+                apply_knockback.after(deal_damage),
+                tick_hit_stun,
+                color_mob_on_damage,
+                despawn,
+            )
+                .run_if(in_state(AppState::Playing)),
+        );
     }
 }
 
@@ -32,17 +47,26 @@ pub fn deal_damage_on_collision(
     mut attacked_entities: Query<(&CollidingEntities, Entity, &AttackableFrom), With<Health>>,
     time: Res<Time>,
     mut event_writer: EventWriter<EntityDamageEvent>,
-    mut attacking_entities: Query<(&mut AttackTimer, &Damage, &EntityType), Without<Player>>,
+    mut attacking_entities: Query<
+        (
+            &mut AttackTimer,
+            &Damage,
+            &EntityType,
+            Option<&StatusEffects>,
+        ),
+        Without<Player>,
+    >,
 ) {
     for (attacking, damageable_entity, attackable_from) in attacked_entities.iter_mut() {
         for attacking_entity in &attacking.0 {
-            let Ok((mut timer, damage, entity_type)) =
+            let Ok((mut timer, damage, entity_type, status)) =
                 attacking_entities.get_mut(*attacking_entity)
             else {
                 continue;
             };
 
-            if !attackable_from.0.contains(entity_type) {
+            // frozen or stunned mobs don't hurt on contact
+            if !attackable_from.0.contains(entity_type) || blocks_actions(status) {
                 continue;
             }
 
@@ -54,72 +78,216 @@ pub fn deal_damage_on_collision(
 
             event_writer.send(EntityDamageEvent {
                 entity: damageable_entity,
+                attacker: *attacking_entity,
+                source: Some(*attacking_entity),
                 damage: damage.0,
+                damage_type: damage.1,
+                knockback: None,
+                status: None,
             });
         }
     }
 }
 
+/// Applies every damage event of the frame, each death is reported once.
+/// Knockback and status effects only go through for hits that aren't blocked
+/// by immunity or a cool down.
 pub fn deal_damage(
     mut commands: Commands,
     mut event_writer: EventWriter<EntityDeathEvent>,
     mut event_reader: EventReader<EntityDamageEvent>,
-    mut health: Query<&mut Health, Without<DamageCoolDown>>,
+    mut knockback_events: EventWriter<KnockbackEvent>,
+    mut status_events: EventWriter<ApplyStatusEvent>,
+    mut health: Query<(
+        &mut Health,
+        Option<&Resistances>,
+        Option<&mut DamageCoolDown>,
+    )>,
+    invulnerability: Query<&Invulnerability>,
+    mut new_cool_downs: Local<HashMap<Entity, DamageCoolDown>>,
+    balance: Res<GameBalance>,
 ) {
-    for event in event_reader
-        .read()
-        .dedup_by(|event_a, event_b| event_a.entity == event_b.entity)
-    {
-        let Ok(mut health) = health.get_mut(event.entity) else {
-            return;
+    for event in event_reader.read() {
+        let Ok((mut health, resistances, cool_down)) = health.get_mut(event.entity) else {
+            continue;
+        };
+
+        // already died earlier this frame
+        if health.0 <= 0. {
+            continue;
+        }
+
+        // cool downs inserted this frame only exist as commands yet
+        let blocked = event.source.is_some_and(|source| {
+            cool_down
+                .as_deref()
+                .or(new_cool_downs.get(&event.entity))
+                .is_some_and(|cool_down| cool_down.blocks(source))
+        });
+
+        if blocked {
+            continue;
+        }
+
+        let damage = event.damage
+            * resistances.map_or(1., |resistances| resistances.multiplier(event.damage_type));
+
+        debug!(
+            "{:?} hit {:?} for {} {:?} damage",
+            event.attacker, event.entity, damage, event.damage_type
+        );
+
+        if damage <= 0. {
+            // immune
+            continue;
+        }
+
+        if let Some(source) = event.source {
+            let seconds = invulnerability
+                .get(source)
+                .map_or(balance.damage.cool_down_seconds, |invulnerability| {
+                    invulnerability.0
+                });
+
+            // only now, an empty cool down would already show the damage flash
+            match cool_down {
+                Some(mut cool_down) => cool_down.add(source, seconds),
+                None => new_cool_downs
+                    .entry(event.entity)
+                    .or_default()
+                    .add(source, seconds),
+            }
+        }
+
+        health.0 -= damage;
+
+        if let Some(impulse) = event.knockback {
+            knockback_events.send(KnockbackEvent {
+                entity: event.entity,
+                impulse,
+            });
+        }
+
+        if let Some(status) = &event.status {
+            status_events.send(status.event(event.entity, event.attacker));
+        }
+
+        if health.0 <= 0. {
+            debug!(
+                "{:?} killed {:?} with {} overkill",
+                event.attacker, event.entity, -health.0
+            );
+            event_writer.send(EntityDeathEvent {
+                entity: event.entity,
+                attacker: Some(event.attacker),
+                overkill: -health.0,
+            });
+        }
+    }
+
+    for (entity, cool_down) in new_cool_downs.drain() {
+        commands.entity(entity).insert(cool_down);
+    }
+}
+
+/// Pushes entities away from hits and stuns them for a moment so they don't steer right back.
+pub fn apply_knockback(
+    mut commands: Commands,
+    mut knockback_events: EventReader<KnockbackEvent>,
+    mut entities: Query<(&mut LinearVelocity, Option<&KnockbackResistance>)>,
+    balance: Res<GameBalance>,
+) {
+    for event in knockback_events.read() {
+        let Ok((mut velocity, resistance)) = entities.get_mut(event.entity) else {
+            continue;
         };
 
-        if health.0 - event.damage <= 0. {
-            event_writer.send(EntityDeathEvent(event.entity));
-            return;
+        let factor = 1. - resistance.map_or(0., |resistance| resistance.0.clamp(0., 1.));
+
+        if factor <= 0. {
+            continue;
         }
 
+        velocity.0 += event.impulse * factor;
         commands
             .entity(event.entity)
-            .insert(DamageCoolDown::new(0.3));
-        health.0 -= event.damage;
+            .insert(HitStun::new(balance.damage.hit_stun_seconds));
     }
 }
 
+pub fn tick_hit_stun(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut entities: Query<(Entity, &mut HitStun)>,
+) {
+    for (entity, mut hit_stun) in entities.iter_mut() {
+        if hit_stun.0.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<HitStun>();
+        }
+    }
+}
+
+/// The player is not despawned, `kill_player` marks them `Dead` instead.
 pub fn remove_dead_entities(
     mut event_reader: EventReader<EntityDeathEvent>,
     mut commands: Commands,
+    players: Query<(), With<Player>>,
 ) {
-    for dead_entity in event_reader.read().dedup() {
-        if let Some(mut entity) = commands.get_entity(dead_entity.0) {
-            entity.despawn()
+    for dead_entity in event_reader.read().map(|event| event.entity).unique() {
+        if players.contains(dead_entity) {
+            continue;
+        }
+
+        if let Some(entity) = commands.get_entity(dead_entity) {
+            entity.despawn_recursive()
         }
     }
 }
 
 pub fn tick_damage_cool_down(time: Res<Time>, mut entities: Query<&mut DamageCoolDown>) {
     for mut cool_down in entities.iter_mut() {
-        cool_down.0.tick(time.delta());
+        for timer in cool_down.0.values_mut() {
+            timer.tick(time.delta());
+        }
+
+        cool_down.0.retain(|_, timer| !timer.finished());
     }
 }
 
 pub fn remove_damage_cool_down(mut commands: Commands, entities: Query<(Entity, &DamageCoolDown)>) {
     for (entity, _) in entities
         .iter()
-        .filter(|(_, cool_down)| cool_down.0.finished())
+        .filter(|(_, cool_down)| cool_down.0.is_empty())
     {
         commands.entity(entity).remove::<DamageCoolDown>();
     }
 }
 
 pub fn color_mob_on_damage(
-    mut mobs: Query<(Option<&DamageCoolDown>, &mut Sprite), Or<(With<Player>, With<Mob>)>>,
+    mut mobs: Query<
+        (
+            Option<&DamageCoolDown>,
+            Option<&MobAi>,
+            Option<&Boss>,
+            Option<&StatusEffects>,
+            &BaseColor,
+            &mut Sprite,
+        ),
+        Or<(With<Player>, With<Mob>)>,
+    >,
 ) {
-    for (cool_down, mut sprite) in mobs.iter_mut() {
+    for (cool_down, ai, boss, status, base_color, mut sprite) in mobs.iter_mut() {
         if cool_down.is_some() {
             sprite.color = Color::rgb(1., 0.75, 0.25)
+        } else if ai.is_some_and(|ai| ai.state == MobState::WindUp)
+            || boss.is_some_and(Boss::is_telegraphing)
+        {
+            // telegraph the coming attack
+            sprite.color = WIND_UP_COLOR
+        } else if let Some(tint) = status.and_then(StatusEffects::tint) {
+            sprite.color = tint
         } else {
-            sprite.color = Color::rgb(0.25, 0.75, 0.25)
+            sprite.color = base_color.0
         }
     }
 }
@@ -137,3 +305,21 @@ pub fn despawn(
         }
     }
 }
+
+/// Everything that belongs to a run, the world itself is torn down by `teardown_world`.
+fn despawn_run_entities(
+    mut commands: Commands,
+    entities: Query<
+        Entity,
+        Or<(
+            With<Player>,
+            With<Mob>,
+            With<Projectile>,
+            With<LongTimeAttack>,
+        )>,
+    >,
+) {
+    for entity in entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -1,3 +1,5 @@
+use crate::entities::data::DamageType;
+use crate::entities::status::StatusOnHit;
 use bevy::app::{App, Plugin};
 use bevy::math::Vec2;
 use bevy::prelude::{Entity, Event};
@@ -8,6 +10,7 @@ impl Plugin for EventPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<EntityDamageEvent>()
             .add_event::<EntityDeathEvent>()
+            .add_event::<KnockbackEvent>()
             .add_event::<FireballExplosionEvent>()
             .add_event::<PlayerMoveEvent>();
     }
@@ -16,11 +19,35 @@ impl Plugin for EventPlugin {
 #[derive(Event)]
 pub struct EntityDamageEvent {
     pub entity: Entity,
+    /// who gets the credit for the hit
+    pub attacker: Entity,
+    /// what actually hit, invulnerability is per source, `None` ignores it like damage over time
+    pub source: Option<Entity>,
+    /// before resistances are applied
     pub damage: f64,
+    pub damage_type: DamageType,
+    /// pushes the entity only if the hit lands, see `deal_damage`
+    pub knockback: Option<Vec2>,
+    /// applied only if the hit lands as well
+    pub status: Option<StatusOnHit>,
 }
 
+/// Sent once per entity, when its health first drops to zero.
 #[derive(Event, PartialEq)]
-pub struct EntityDeathEvent(pub Entity);
+pub struct EntityDeathEvent {
+    pub entity: Entity,
+    /// who landed the killing blow, `None` if nothing killed it, e.g. a projectile hitting a wall
+    pub attacker: Option<Entity>,
+    /// damage beyond what was left of the health
+    pub overkill: f64,
+}
+
+#[derive(Event)]
+pub struct KnockbackEvent {
+    pub entity: Entity,
+    /// added to the velocity before knockback resistance is applied
+    pub impulse: Vec2,
+}
 
 #[derive(Event)]
 pub struct PlayerMoveEvent(pub Vec2);
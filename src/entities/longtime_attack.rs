@@ -1,32 +1,54 @@
-use crate::entities::data::{AttackableFrom, Damage, EntityType, Health};
+use crate::entities::data::{
+    AttackableFrom, Attacker, Damage, EntityType, Health, Knockback, KnockbackOrigin,
+};
 use crate::entities::event::EntityDamageEvent;
+use crate::entities::status::StatusOnHit;
+use crate::AppState;
 use bevy::app::{App, Plugin, Update};
-use bevy::prelude::{Component, Entity, EventWriter, Query, With};
+use bevy::prelude::{
+    in_state, Component, Entity, EventWriter, IntoSystemConfigs, Query, Transform, Vec3Swizzles,
+    With,
+};
 use bevy_xpbd_2d::prelude::CollidingEntities;
 
 pub struct LongTimeAttackPlugin;
 
 impl Plugin for LongTimeAttackPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, damage_entities);
+        app.add_systems(Update, damage_entities.run_if(in_state(AppState::Playing)));
     }
 }
 
 #[derive(Component)]
 pub struct LongTimeAttack {
     pub damaged_entities: Vec<Entity>,
+    /// who attacks, only entities attackable from it get damaged
+    pub from: EntityType,
 }
 
 fn damage_entities(
-    mut entity_attacks: Query<(&CollidingEntities, &mut LongTimeAttack, &Damage)>,
+    mut entity_attacks: Query<(
+        Entity,
+        &CollidingEntities,
+        &mut LongTimeAttack,
+        &Damage,
+        Option<&Attacker>,
+        Option<&StatusOnHit>,
+        Option<&Knockback>,
+    )>,
     attackable_from: Query<&AttackableFrom, With<Health>>,
+    transforms: Query<&Transform>,
     mut event_writer: EventWriter<EntityDamageEvent>,
 ) {
-    for (touching_entities, mut entity_attack, damage) in entity_attacks.iter_mut() {
+    for (attack, touching_entities, mut entity_attack, damage, attacker, status, knockback) in
+        entity_attacks.iter_mut()
+    {
+        let attacker = attacker.map_or(attack, |attacker| attacker.0);
+
         for touching_entity in &touching_entities.0 {
             let can_be_attacked = attackable_from
                 .get(*touching_entity)
-                .map(|attackable_from| attackable_from.0.contains(&EntityType::Player))
+                .map(|attackable_from| attackable_from.0.contains(&entity_attack.from))
                 .unwrap_or(false);
 
             if !can_be_attacked || entity_attack.damaged_entities.contains(touching_entity) {
@@ -34,9 +56,33 @@ fn damage_entities(
             }
 
             entity_attack.damaged_entities.push(*touching_entity);
+
+            let knockback = knockback.and_then(|knockback| {
+                let origin = match knockback.origin {
+                    KnockbackOrigin::Attacker => attacker,
+                    KnockbackOrigin::Center => attack,
+                };
+
+                let (Ok(origin), Ok(target)) =
+                    (transforms.get(origin), transforms.get(*touching_entity))
+                else {
+                    return None;
+                };
+
+                let direction =
+                    (target.translation.xy() - origin.translation.xy()).normalize_or_zero();
+
+                Some(direction * knockback.strength)
+            });
+
             event_writer.send(EntityDamageEvent {
                 entity: *touching_entity,
+                attacker,
+                source: Some(attack),
                 damage: damage.0,
+                damage_type: damage.1,
+                knockback,
+                status: status.cloned(),
             });
         }
     }
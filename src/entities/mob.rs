@@ -1,73 +1,289 @@
 use bevy::prelude::*;
 
+use crate::balance::GameBalance;
+use crate::entities::ai::{
+    update_mob_ai, MobAi, MobState, LUNGE_SPEED_FACTOR, WANDER_SPEED_FACTOR,
+};
+use crate::entities::boss::BossStats;
 use crate::entities::data::{
-    AttackTimer, AttackableFrom, Damage, EntityType, Health, Mob, Player, MOB_DAMAGE, MOB_HEALTH,
-    MOB_RADIUS, MOB_SPEED,
+    AttackTimer, AttackableFrom, BaseColor, Damage, Dead, EntityType, Health, HitStun,
+    Invulnerability, KnockbackResistance, Mob, MovementSpeed, Player, Resistances,
+};
+use crate::entities::entity::apply_knockback;
+use crate::entities::mob_kind::{
+    apply_mob_registry, MobBehaviour, MobKind, MobKinds, MobRegistryReloaded, MobStats,
+    ProjectileStats,
 };
-use crate::PhysicsLayers;
+use crate::entities::status::{speed_multiplier, StatusEffects};
+use crate::entities::steering::{build_mob_spatial_hash, MobSpatialHash, Surround};
+use crate::entities::waves::WaveMob;
+use crate::world::nav::{FlowField, NavGrid};
+use crate::{AppState, PhysicsLayers};
 use bevy::math::vec2;
 use bevy_xpbd_2d::prelude::*;
-use rand::Rng;
 use std::ops::Mul;
+use std::time::Duration;
 
 pub struct MobPlugin;
 
 impl Plugin for MobPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, move_mob)
-            .add_systems(Startup, spawn_mobs);
+        app.add_systems(
+            Update,
+            move_mob
+                .after(update_mob_ai)
+                .after(build_mob_spatial_hash)
+                // hit stun is inserted with commands, so it has to be applied first
+                .after(apply_knockback)
+                .run_if(in_state(AppState::Playing)),
+        )
+        .add_systems(
+            Update,
+            (
+                // the reloaded registry is inserted with commands
+                update_mob_stats
+                    .after(apply_mob_registry)
+                    .run_if(on_event::<MobRegistryReloaded>()),
+                update_mob_damping.run_if(resource_exists_and_changed::<GameBalance>),
+            ),
+        );
     }
 }
 
-pub fn spawn_mobs(mut commands: Commands) {
-    let min_x = -300.;
-    let max_x = 300.;
-
-    let mut random = rand::thread_rng();
-
-    for _i in 0..10 {
-        let random_x = random.gen_range(min_x..max_x);
-
-        commands.spawn((
-            Mob,
-            EntityType::Mob,
-            AttackableFrom(vec![EntityType::Player, EntityType::Spell]),
-            Damage(MOB_DAMAGE as f64),
-            Health(MOB_HEALTH as f64),
-            AttackTimer::new_attack_timer(2.),
-            RigidBody::Dynamic,
-            Restitution::new(0.),
-            Collider::circle(MOB_RADIUS),
-            CollisionLayers::new([PhysicsLayers::Mob, PhysicsLayers::Entity], LayerMask::ALL),
-            LinearVelocity(vec2(0., 0.)),
-            LinearDamping(20.),
-            LockedAxes::ROTATION_LOCKED,
-            ColliderDensity(0.),
-            SpriteBundle {
-                sprite: Sprite {
-                    color: Color::rgb(0.25, 0.75, 0.25),
-                    custom_size: Some(Vec2::new(MOB_RADIUS * 2., MOB_RADIUS * 2.)),
-                    ..default()
-                },
-                transform: Transform::from_xyz(random_x, -300., 0.),
+/// Ranged mobs hold still while they are this close to their preferred distance.
+pub static RANGED_DISTANCE_TOLERANCE: f32 = 20.;
+
+pub fn spawn_mob<'a>(
+    commands: &'a mut Commands,
+    kind: MobKind,
+    stats: &MobStats,
+    position: Vec2,
+    balance: &GameBalance,
+) -> EntityCommands<'a> {
+    let mut mob = commands.spawn((
+        Mob,
+        kind,
+        stats.behaviour,
+        EntityType::Mob,
+        AttackableFrom(vec![EntityType::Player, EntityType::Spell]),
+        Damage(stats.damage, stats.damage_type),
+        Resistances(stats.resistances.clone()),
+        KnockbackResistance(stats.knockback_resistance),
+        Health(stats.health),
+        MovementSpeed(stats.speed),
+        BaseColor(stats.color()),
+        MobAi::new(stats, position),
+        AttackTimer::new_attack_timer(stats.attack_interval),
+        RigidBody::Dynamic,
+        Restitution::new(0.),
+        Collider::circle(stats.radius),
+        CollisionLayers::new([PhysicsLayers::Mob, PhysicsLayers::Entity], LayerMask::ALL),
+        LinearVelocity(vec2(0., 0.)),
+        LinearDamping(balance.mobs.linear_damping),
+        LockedAxes::ROTATION_LOCKED,
+        ColliderDensity(0.),
+        SpriteBundle {
+            sprite: Sprite {
+                color: stats.color(),
+                custom_size: Some(Vec2::new(stats.radius * 2., stats.radius * 2.)),
                 ..default()
             },
-        ));
+            transform: Transform::from_translation(position.extend(0.)),
+            ..default()
+        },
+    ));
+
+    if stats.surround {
+        mob.insert(Surround::random());
     }
+
+    if let Some(seconds) = stats.invulnerability {
+        mob.insert(Invulnerability(seconds));
+    }
+
+    if let Some(projectile) = &stats.projectile {
+        mob.insert(projectile.clone());
+    }
+
+    mob
 }
 
 pub fn move_mob(
-    mut mob_query: Query<(&mut LinearVelocity, &Transform), (With<Mob>, Without<Player>)>,
-    player_query: Query<&Transform, With<Player>>,
+    mut mob_query: Query<
+        (
+            Entity,
+            &mut LinearVelocity,
+            &Transform,
+            &MovementSpeed,
+            &MobBehaviour,
+            &MobAi,
+            Option<&Surround>,
+            Option<&StatusEffects>,
+        ),
+        // let knockback play out instead of steering right away
+        (With<Mob>, Without<Player>, Without<HitStun>),
+    >,
+    player_query: Query<&Transform, (With<Player>, Without<Dead>)>,
+    nav_grid: Res<NavGrid>,
+    flow_field: Res<FlowField>,
+    spatial_hash: Res<MobSpatialHash>,
+    balance: Res<GameBalance>,
+) {
+    let player_position = player_query
+        .get_single()
+        .ok()
+        .map(|transform| transform.translation.xy());
+
+    for (entity, mut linear_velocity, transform, speed, behaviour, ai, surround, status) in
+        mob_query.iter_mut()
+    {
+        let position = transform.translation.xy();
+
+        let (seek, speed_factor) = match ai.state {
+            // idle mobs still drift apart from each other
+            MobState::Idle | MobState::Aggro => (Vec2::ZERO, WANDER_SPEED_FACTOR),
+            MobState::WindUp => (Vec2::ZERO, 0.),
+            MobState::Wander => (
+                (ai.wander_target - position).normalize_or_zero(),
+                WANDER_SPEED_FACTOR,
+            ),
+            MobState::GiveUp => (
+                (ai.home - position).normalize_or_zero(),
+                WANDER_SPEED_FACTOR,
+            ),
+            // ranged mobs stand still while they shoot
+            MobState::Attack if matches!(behaviour, MobBehaviour::Ranged { .. }) => {
+                (Vec2::ZERO, 0.)
+            }
+            MobState::Attack => (ai.attack_direction, LUNGE_SPEED_FACTOR),
+            MobState::Chase => {
+                let Some(player_position) = player_position else {
+                    continue;
+                };
+
+                let to_player = player_position - position;
+                let path = flow_field
+                    .direction(&nav_grid, position)
+                    .unwrap_or(to_player.normalize_or_zero());
+                let surround = surround.map_or(Vec2::ZERO, |surround| surround.steer(to_player));
+
+                (chase_direction(to_player, path, behaviour) + surround, 1.)
+            }
+        };
+
+        let steering = match ai.state {
+            MobState::WindUp | MobState::Attack => Vec2::ZERO,
+            _ => spatial_hash.flocking(entity, position),
+        };
+
+        let vec = (seek + steering)
+            .clamp_length_max(1.)
+            .mul(speed.0 * speed_factor * speed_multiplier(status, &balance.status));
+
+        linear_velocity.x = vec.x;
+        linear_velocity.y = vec.y;
+    }
+}
+
+/// Direction of a chasing mob, `path` leads towards the player around obstacles.
+fn chase_direction(to_player: Vec2, path: Vec2, behaviour: &MobBehaviour) -> Vec2 {
+    match behaviour {
+        MobBehaviour::Ranged { preferred_distance } => {
+            let offset = to_player.length() - preferred_distance;
+
+            if offset.abs() < RANGED_DISTANCE_TOLERANCE {
+                Vec2::ZERO
+            } else if offset > 0. {
+                path
+            } else {
+                -to_player.normalize_or_zero()
+            }
+        }
+        MobBehaviour::Melee | MobBehaviour::Boss => path,
+    }
+}
+
+/// Applies a reloaded balance to the existing mobs, the rest of their stats is per kind.
+fn update_mob_damping(mut mobs: Query<&mut LinearDamping, With<Mob>>, balance: Res<GameBalance>) {
+    for mut damping in mobs.iter_mut() {
+        damping.0 = balance.mobs.linear_damping;
+    }
+}
+
+/// Applies reloaded stats to mobs that are already alive. Health and size stay as they are,
+/// so a reload doesn't heal mobs or push them into walls.
+fn update_mob_stats(
+    mut commands: Commands,
+    mut reloaded: EventReader<MobRegistryReloaded>,
+    mob_kinds: MobKinds,
+    mut mobs: Query<
+        (
+            Entity,
+            &MobKind,
+            &mut MovementSpeed,
+            &mut Damage,
+            &mut Resistances,
+            &mut KnockbackResistance,
+            &mut BaseColor,
+            &mut AttackTimer,
+            &mut MobAi,
+            Option<&WaveMob>,
+            Has<BossStats>,
+        ),
+        With<Mob>,
+    >,
 ) {
-    if let Ok(player_transform) = player_query.get_single() {
-        for (mut linear_velocity, transform) in mob_query.iter_mut() {
-            let vec = (player_transform.translation - transform.translation)
-                .normalize_or_zero()
-                .mul(MOB_SPEED);
-
-            linear_velocity.x = vec.x;
-            linear_velocity.y = vec.y;
+    reloaded.clear();
+
+    for (
+        mob,
+        kind,
+        mut speed,
+        mut damage,
+        mut resistances,
+        mut knockback_resistance,
+        mut base_color,
+        mut attack_timer,
+        mut ai,
+        wave_mob,
+        is_boss,
+    ) in mobs.iter_mut()
+    {
+        let Some(stats) = mob_kinds.stats(*kind) else {
+            continue;
+        };
+
+        speed.0 = stats.speed;
+        // wave mobs keep the damage scaling of their wave
+        let damage_multiplier = wave_mob.map_or(1., |wave_mob| wave_mob.damage_multiplier);
+        *damage = Damage(stats.damage * damage_multiplier, stats.damage_type);
+        resistances.0 = stats.resistances.clone();
+        knockback_resistance.0 = stats.knockback_resistance;
+        base_color.0 = stats.color();
+        attack_timer
+            .0
+            .set_duration(Duration::from_secs_f32(stats.attack_interval));
+        ai.attack_cooldown
+            .set_duration(Duration::from_secs_f32(stats.attack_interval));
+        ai.detection_radius = stats.detection_radius;
+        ai.give_up_radius = stats.give_up_radius;
+        ai.attack_range = stats.attack_range;
+        ai.wind_up = stats.wind_up;
+
+        let mut mob = commands.entity(mob);
+
+        match stats.invulnerability {
+            Some(seconds) => mob.insert(Invulnerability(seconds)),
+            None => mob.remove::<Invulnerability>(),
+        };
+
+        match &stats.projectile {
+            Some(projectile) => mob.insert(projectile.clone()),
+            None => mob.remove::<ProjectileStats>(),
+        };
+
+        if let (true, Some(boss)) = (is_boss, &stats.boss) {
+            mob.insert(boss.clone());
         }
     }
 }
@@ -0,0 +1,380 @@
+use crate::entities::boss::{BossAttack, BossStats};
+use crate::entities::data::DamageType;
+use crate::ron_asset::{ron_diff, RonAssetLoader};
+use bevy::app::{App, Plugin, PreStartup, Update};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+pub struct MobKindPlugin;
+
+impl Plugin for MobKindPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<MobRegistry>()
+            .register_asset_loader(RonAssetLoader::<MobRegistry>::new(&["mobs.ron"]))
+            .add_event::<MobRegistryReloaded>()
+            .add_systems(PreStartup, load_mob_registry)
+            .add_systems(Update, apply_mob_registry);
+    }
+}
+
+pub static MOB_REGISTRY_PATH: &str = "mobs.ron";
+
+#[derive(PartialEq, Eq, Hash, Component, Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum MobKind {
+    /// fast and weak
+    #[default]
+    Wolf,
+    /// slow and tanky
+    IceGolem,
+    /// keeps its distance
+    Wisp,
+    /// boss
+    Yeti,
+}
+
+pub static MOB_KINDS: [MobKind; 4] = [
+    MobKind::Wolf,
+    MobKind::IceGolem,
+    MobKind::Wisp,
+    MobKind::Yeti,
+];
+
+#[derive(PartialEq, Component, Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum MobBehaviour {
+    /// runs straight at the player
+    Melee,
+    /// stays around `preferred_distance` away from the player
+    Ranged {
+        preferred_distance: f32,
+    },
+    Boss,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MobStats {
+    pub speed: f32,
+    pub radius: f32,
+    pub health: f64,
+    pub damage: f64,
+    #[serde(default)]
+    pub damage_type: DamageType,
+    /// multiplier on incoming damage per type, see `Resistances`
+    #[serde(default)]
+    pub resistances: HashMap<DamageType, f64>,
+    /// fraction of knockback ignored, see `KnockbackResistance`
+    #[serde(default)]
+    pub knockback_resistance: f32,
+    /// seconds the player can't be hurt by this mob again after a hit, see `Invulnerability`
+    #[serde(default)]
+    pub invulnerability: Option<f32>,
+    /// seconds between two contact attacks, also the cooldown between wind-ups
+    pub attack_interval: f32,
+    /// the mob notices the player within this distance
+    pub detection_radius: f32,
+    /// the mob gives up the chase once the player stays further away than this
+    pub give_up_radius: f32,
+    /// mobs start their attack wind-up within this distance
+    pub attack_range: f32,
+    /// seconds of the attack wind-up
+    pub wind_up: f32,
+    /// spread around the player instead of all coming from the same side
+    #[serde(default)]
+    pub surround: bool,
+    pub color: (f32, f32, f32),
+    pub behaviour: MobBehaviour,
+    /// what ranged mobs fire at the end of their wind-up
+    #[serde(default)]
+    pub projectile: Option<ProjectileStats>,
+    /// phases and attack patterns, only set for bosses
+    #[serde(default)]
+    pub boss: Option<BossStats>,
+}
+
+#[derive(Component, Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectileStats {
+    pub speed: f32,
+    pub radius: f32,
+    pub color: (f32, f32, f32),
+}
+
+impl MobStats {
+    pub fn color(&self) -> Color {
+        Color::rgb(self.color.0, self.color.1, self.color.2)
+    }
+}
+
+/// Stats of every mob kind, loaded from `assets/mobs.ron`.
+/// Inserted as a resource once the file is loaded and valid, see `apply_mob_registry`.
+#[derive(Asset, Resource, TypePath, Clone, Serialize, Deserialize)]
+pub struct MobRegistry {
+    pub kinds: HashMap<MobKind, MobStats>,
+}
+
+#[derive(Debug)]
+pub enum MobRegistryError {
+    MissingKind(MobKind),
+    NotPositive {
+        kind: MobKind,
+        field: String,
+        value: f64,
+    },
+    Negative {
+        kind: MobKind,
+        field: String,
+        value: f64,
+    },
+    Empty {
+        kind: MobKind,
+        field: String,
+    },
+}
+
+impl Display for MobRegistryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MobRegistryError::MissingKind(kind) => write!(f, "kinds has no entry for {kind:?}"),
+            MobRegistryError::NotPositive { kind, field, value } => {
+                write!(f, "{kind:?}.{field} has to be greater than 0, got {value}")
+            }
+            MobRegistryError::Negative { kind, field, value } => {
+                write!(f, "{kind:?}.{field} can't be negative, got {value}")
+            }
+            MobRegistryError::Empty { kind, field } => {
+                write!(f, "{kind:?}.{field} needs at least one entry")
+            }
+        }
+    }
+}
+
+impl std::error::Error for MobRegistryError {}
+
+impl MobRegistry {
+    /// Every problem in the file at once, an invalid registry is never applied.
+    pub fn validate(&self) -> Vec<MobRegistryError> {
+        let mut errors = vec![];
+
+        for kind in MOB_KINDS {
+            let Some(stats) = self.kinds.get(&kind) else {
+                errors.push(MobRegistryError::MissingKind(kind));
+                continue;
+            };
+
+            let mut positive = vec![
+                ("radius".to_string(), stats.radius as f64),
+                ("health".to_string(), stats.health),
+                ("attack_interval".to_string(), stats.attack_interval as f64),
+            ];
+
+            let mut not_negative = vec![
+                ("speed".to_string(), stats.speed as f64),
+                ("damage".to_string(), stats.damage),
+                (
+                    "detection_radius".to_string(),
+                    stats.detection_radius as f64,
+                ),
+                ("give_up_radius".to_string(), stats.give_up_radius as f64),
+                ("attack_range".to_string(), stats.attack_range as f64),
+                ("wind_up".to_string(), stats.wind_up as f64),
+            ];
+
+            if let Some(boss) = &stats.boss {
+                if boss.phases.is_empty() {
+                    errors.push(MobRegistryError::Empty {
+                        kind,
+                        field: "boss.phases".to_string(),
+                    });
+                }
+
+                for (index, phase) in boss.phases.iter().enumerate() {
+                    let field = |name: &str| format!("boss.phases.{index}.{name}");
+
+                    if phase.attacks.is_empty() {
+                        errors.push(MobRegistryError::Empty {
+                            kind,
+                            field: field("attacks"),
+                        });
+                    }
+
+                    positive.push((field("attack_interval"), phase.attack_interval as f64));
+                    not_negative.push((field("telegraph"), phase.telegraph as f64));
+
+                    for (attack_index, attack) in phase.attacks.iter().enumerate() {
+                        let field = |name: &str| field(&format!("attacks.{attack_index}.{name}"));
+
+                        match attack {
+                            BossAttack::Charge { speed, seconds } => {
+                                not_negative.push((field("speed"), *speed as f64));
+                                positive.push((field("seconds"), *seconds as f64));
+                            }
+                            BossAttack::Slam { radius, damage, .. } => {
+                                positive.push((field("radius"), *radius as f64));
+                                not_negative.push((field("damage"), *damage));
+                            }
+                            BossAttack::Summon { .. } => {}
+                        }
+                    }
+                }
+            }
+
+            for (field, value) in positive {
+                if value.is_nan() || value <= 0. {
+                    errors.push(MobRegistryError::NotPositive { kind, field, value });
+                }
+            }
+
+            for (field, value) in not_negative {
+                if value.is_nan() || value < 0. {
+                    errors.push(MobRegistryError::Negative { kind, field, value });
+                }
+            }
+        }
+
+        errors
+    }
+}
+
+/// Sent when a valid edit of `mobs.ron` was applied, existing mobs pick up the new stats in `update_mob_stats`.
+#[derive(Event)]
+pub struct MobRegistryReloaded;
+
+#[derive(Resource)]
+pub struct MobRegistryHandle(pub Handle<MobRegistry>);
+
+/// Access to the stats of the mob kinds, empty until a valid registry finished loading.
+#[derive(SystemParam)]
+pub struct MobKinds<'w> {
+    registry: Option<Res<'w, MobRegistry>>,
+}
+
+impl MobKinds<'_> {
+    /// A valid registry has stats for every kind, so this is only `None` while loading.
+    pub fn stats(&self, kind: MobKind) -> Option<&MobStats> {
+        self.registry.as_ref()?.kinds.get(&kind)
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.registry.is_some()
+    }
+}
+
+/// Picks a kind from a spawn table of kinds and their relative weights.
+pub fn pick_mob_kind(table: &[(MobKind, f32)], rng: &mut impl Rng) -> Option<MobKind> {
+    table
+        .choose_weighted(rng, |(_, weight)| *weight)
+        .ok()
+        .map(|(kind, _)| *kind)
+}
+
+fn load_mob_registry(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(MobRegistryHandle(asset_server.load(MOB_REGISTRY_PATH)));
+}
+
+/// Copies the loaded registry into the `MobRegistry` resource, unless it has errors.
+/// Also runs when the file is hot reloaded, an invalid edit keeps the previous registry.
+pub fn apply_mob_registry(
+    mut commands: Commands,
+    mut asset_events: EventReader<AssetEvent<MobRegistry>>,
+    mut reloaded: EventWriter<MobRegistryReloaded>,
+    handle: Res<MobRegistryHandle>,
+    registries: Res<Assets<MobRegistry>>,
+    current: Option<Res<MobRegistry>>,
+) {
+    for event in asset_events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event
+        else {
+            continue;
+        };
+
+        if *id != handle.0.id() {
+            continue;
+        }
+
+        let Some(registry) = registries.get(*id) else {
+            continue;
+        };
+
+        let errors = registry.validate();
+
+        if !errors.is_empty() {
+            for error in errors {
+                error!("invalid {MOB_REGISTRY_PATH}: {error}");
+            }
+
+            continue;
+        }
+
+        match &current {
+            None => info!("loaded the mob kinds from {MOB_REGISTRY_PATH}"),
+            Some(current) => {
+                let changes = ron_diff(&**current, registry);
+
+                // a reload can report both events for the same change
+                if changes.is_empty() {
+                    continue;
+                }
+
+                info!("reloaded {MOB_REGISTRY_PATH}: {}", changes.join(", "));
+                reloaded.send(MobRegistryReloaded);
+            }
+        }
+
+        commands.insert_resource(registry.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iter_tools::Itertools;
+
+    fn registry() -> MobRegistry {
+        ron::from_str(include_str!("../../assets/mobs.ron")).unwrap()
+    }
+
+    #[test]
+    fn shipped_registry_is_valid() {
+        let errors = registry().validate();
+
+        assert!(errors.is_empty(), "{errors:?}");
+    }
+
+    #[test]
+    fn reports_bad_mob_and_boss_stats() {
+        let mut registry = registry();
+        registry.kinds.remove(&MobKind::Wisp);
+
+        let wolf = registry.kinds.get_mut(&MobKind::Wolf).unwrap();
+        wolf.health = 0.;
+        wolf.wind_up = -1.;
+
+        let boss = registry
+            .kinds
+            .get_mut(&MobKind::Yeti)
+            .unwrap()
+            .boss
+            .as_mut()
+            .unwrap();
+        boss.phases[0].telegraph = -1.;
+        boss.phases[1].attacks.clear();
+
+        let fields = registry
+            .validate()
+            .into_iter()
+            .map(|error| error.to_string())
+            .collect_vec();
+
+        assert_eq!(fields.len(), 5, "{fields:?}");
+        assert!(fields.contains(&"kinds has no entry for Wisp".to_string()));
+        assert!(fields.contains(&"Wolf.health has to be greater than 0, got 0".to_string()));
+        assert!(fields.contains(&"Wolf.wind_up can't be negative, got -1".to_string()));
+        assert!(
+            fields.contains(&"Yeti.boss.phases.0.telegraph can't be negative, got -1".to_string())
+        );
+        assert!(fields.contains(&"Yeti.boss.phases.1.attacks needs at least one entry".to_string()));
+    }
+}
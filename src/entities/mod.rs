@@ -1,20 +1,37 @@
+use crate::entities::ai::AiPlugin;
+use crate::entities::boss::BossPlugin;
 use crate::entities::entity::EntityPlugin;
 use crate::entities::event::EventPlugin;
 use crate::entities::longtime_attack::LongTimeAttackPlugin;
 use crate::entities::mob::MobPlugin;
+use crate::entities::mob_kind::MobKindPlugin;
 use crate::entities::player::attacks::AttackPlugin;
+use crate::entities::player::death::DeathPlugin;
 use crate::entities::player::fireball::SpellPlugin;
 use crate::entities::player::melee::MeleePlugin;
 use crate::entities::player::PlayerPlugin;
+use crate::entities::projectile::ProjectilePlugin;
+use crate::entities::spawner::SpawnerPlugin;
+use crate::entities::status::StatusPlugin;
+use crate::entities::steering::SteeringPlugin;
+use crate::entities::waves::WavePlugin;
 use bevy::app::PluginGroupBuilder;
 use bevy::prelude::PluginGroup;
 
+pub mod ai;
+pub mod boss;
 pub mod data;
 pub mod entity;
 pub mod event;
 pub mod longtime_attack;
 pub mod mob;
+pub mod mob_kind;
 pub mod player;
+pub mod projectile;
+pub mod spawner;
+pub mod status;
+pub mod steering;
+pub mod waves;
 
 pub struct EntityPlugins;
 
@@ -23,11 +40,20 @@ impl PluginGroup for EntityPlugins {
         PluginGroupBuilder::start::<Self>()
             .add(EventPlugin)
             .add(EntityPlugin)
+            .add(MobKindPlugin)
+            .add(AiPlugin)
+            .add(SteeringPlugin)
             .add(MobPlugin)
+            .add(SpawnerPlugin)
+            .add(WavePlugin)
+            .add(BossPlugin)
             .add(PlayerPlugin)
+            .add(DeathPlugin)
             .add(AttackPlugin)
             .add(MeleePlugin)
             .add(SpellPlugin)
+            .add(ProjectilePlugin)
             .add(LongTimeAttackPlugin)
+            .add(StatusPlugin)
     }
 }
@@ -1,6 +1,8 @@
-use crate::entities::data::{Player, PlayerAttackCoolDown};
+use crate::entities::data::{Dead, Player, PlayerAttackCoolDown};
+use crate::entities::status::{blocks_actions, StatusEffects};
 
 use crate::ui::{AttackType, InventorySlot, SelectedSlot};
+use crate::AppState;
 
 use bevy::app::{App, Plugin, Update};
 
@@ -10,7 +12,7 @@ pub struct AttackPlugin;
 
 impl Plugin for AttackPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, player_attack)
+        app.add_systems(Update, player_attack.run_if(in_state(AppState::Playing)))
             .add_event::<PlayerAttackEvent>();
     }
 }
@@ -20,17 +22,26 @@ pub struct PlayerAttackEvent(pub AttackType);
 
 fn player_attack(
     mouse_button_input: Res<ButtonInput<MouseButton>>,
-    mut player_query: Query<&mut PlayerAttackCoolDown, With<Player>>,
+    mut player_query: Query<
+        (&mut PlayerAttackCoolDown, Option<&StatusEffects>),
+        (With<Player>, Without<Dead>),
+    >,
     time: Res<Time>,
     mut attack_event: EventWriter<PlayerAttackEvent>,
     selected_inventory_slot: Res<SelectedSlot>,
     inventory: Query<&InventorySlot>,
 ) {
-    let mut attack_timer = player_query.single_mut();
+    // dead players can't attack
+    let Ok((mut attack_timer, status)) = player_query.get_single_mut() else {
+        return;
+    };
 
     attack_timer.0.tick(time.delta());
 
-    if !attack_timer.0.finished() || !mouse_button_input.just_pressed(MouseButton::Left) {
+    if !attack_timer.0.finished()
+        || blocks_actions(status)
+        || !mouse_button_input.just_pressed(MouseButton::Left)
+    {
         return;
     }
 
@@ -0,0 +1,165 @@
+use crate::balance::GameBalance;
+use crate::entities::boss::BossDefeated;
+use crate::entities::data::{DamageCoolDown, Dead, Health, HitStun, Mob, Player};
+use crate::entities::entity::deal_damage;
+use crate::entities::event::EntityDeathEvent;
+use crate::entities::status::StatusEffects;
+use crate::AppState;
+use bevy::app::{App, Plugin, Update};
+use bevy::prelude::*;
+use bevy_xpbd_2d::prelude::*;
+use iter_tools::Itertools;
+
+pub struct DeathPlugin;
+
+impl Plugin for DeathPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunStats>()
+            .init_resource::<Checkpoint>()
+            .add_event::<RespawnPlayerEvent>()
+            .add_event::<RestartRunEvent>()
+            .add_systems(OnExit(AppState::Menu), reset_run_stats)
+            .add_systems(
+                Update,
+                (kill_player, track_run_stats)
+                    .after(deal_damage)
+                    .run_if(in_state(AppState::Playing)),
+            )
+            .add_systems(
+                Update,
+                respawn_player.run_if(on_event::<RespawnPlayerEvent>()),
+            );
+    }
+}
+
+/// What happened during the current run, shown on the game over screen.
+#[derive(Resource, Default, Debug)]
+pub struct RunStats {
+    /// seconds the player was alive
+    pub seconds: f32,
+    pub mobs_killed: u32,
+    pub bosses_defeated: u32,
+    pub deaths: u32,
+}
+
+/// Where the player respawns, the origin until the game is saved or loaded.
+#[derive(Resource, Default)]
+pub struct Checkpoint(pub Vec2);
+
+/// Brings the dead player back at the checkpoint, the world stays as it is.
+#[derive(Event)]
+pub struct RespawnPlayerEvent;
+
+/// Throws away the current world and starts over with a new one, see `skip_menu_on_restart`.
+#[derive(Event)]
+pub struct RestartRunEvent;
+
+/// Marks the player dead instead of despawning them, see `remove_dead_entities`.
+fn kill_player(
+    mut commands: Commands,
+    mut death_events: EventReader<EntityDeathEvent>,
+    mut player_query: Query<
+        (Entity, &mut LinearVelocity, &mut Visibility),
+        (With<Player>, Without<Dead>),
+    >,
+    mut stats: ResMut<RunStats>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let Ok((player, mut velocity, mut visibility)) = player_query.get_single_mut() else {
+        death_events.clear();
+        return;
+    };
+
+    if !death_events.read().any(|event| event.entity == player) {
+        return;
+    }
+
+    velocity.0 = Vec2::ZERO;
+    *visibility = Visibility::Hidden;
+    // mobs and projectiles pass through the body until `respawn_player` puts the collider back
+    commands
+        .entity(player)
+        .insert((Dead, RigidBody::Static))
+        .remove::<Collider>();
+
+    stats.deaths += 1;
+
+    info!(
+        "the player died after {:.0}s with {} kills",
+        stats.seconds, stats.mobs_killed
+    );
+    next_state.set(AppState::GameOver);
+}
+
+fn track_run_stats(
+    time: Res<Time>,
+    mut death_events: EventReader<EntityDeathEvent>,
+    mut boss_defeated: EventReader<BossDefeated>,
+    mobs: Query<(), With<Mob>>,
+    player_query: Query<(), (With<Player>, Without<Dead>)>,
+    mut stats: ResMut<RunStats>,
+) {
+    if player_query.get_single().is_ok() {
+        stats.seconds += time.delta_seconds();
+    }
+
+    for event in death_events.read().unique_by(|event| event.entity) {
+        let by_player = event
+            .attacker
+            .is_some_and(|attacker| player_query.contains(attacker));
+
+        if by_player && mobs.contains(event.entity) {
+            stats.mobs_killed += 1;
+        }
+    }
+
+    stats.bosses_defeated += boss_defeated.read().count() as u32;
+}
+
+fn reset_run_stats(mut stats: ResMut<RunStats>, mut checkpoint: ResMut<Checkpoint>) {
+    *stats = RunStats::default();
+    checkpoint.0 = Vec2::ZERO;
+}
+
+fn respawn_player(
+    mut commands: Commands,
+    mut respawn_events: EventReader<RespawnPlayerEvent>,
+    checkpoint: Res<Checkpoint>,
+    balance: Res<GameBalance>,
+    mut player_query: Query<
+        (
+            Entity,
+            &mut Health,
+            &mut Transform,
+            &mut LinearVelocity,
+            &mut Visibility,
+        ),
+        With<Player>,
+    >,
+) {
+    if respawn_events.read().count() == 0 {
+        return;
+    }
+
+    let Ok((player, mut health, mut transform, mut velocity, mut visibility)) =
+        player_query.get_single_mut()
+    else {
+        return;
+    };
+
+    health.0 = balance.player.max_health;
+    transform.translation = checkpoint.0.extend(transform.translation.z);
+    velocity.0 = Vec2::ZERO;
+    *visibility = Visibility::Inherited;
+
+    commands
+        .entity(player)
+        .insert((
+            Position(checkpoint.0),
+            RigidBody::Dynamic,
+            Collider::circle(balance.player.radius),
+        ))
+        .remove::<(Dead, StatusEffects, HitStun, DamageCoolDown)>();
+
+    info!("the player respawned at {}", checkpoint.0);
+}
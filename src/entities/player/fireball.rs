@@ -1,11 +1,11 @@
+use crate::balance::GameBalance;
 use crate::entities::data::{
-    Damage, DespawnTimer, EntityType, Fireball, Player, FIRE_BALL_DAMAGE, FIRE_BALL_RADIUS,
-    FIRE_BALL_SPEED,
+    Attacker, Damage, DamageType, DespawnTimer, EntityType, Fireball, Knockback, KnockbackOrigin,
+    Player, Projectile,
 };
-use crate::entities::event::EntityDeathEvent;
 use crate::entities::player::attacks::PlayerAttackEvent;
 use crate::ui::AttackType;
-use crate::PhysicsLayers;
+use crate::{AppState, PhysicsLayers};
 use bevy::app::{App, Update};
 
 use crate::entities::longtime_attack::LongTimeAttack;
@@ -26,23 +26,29 @@ impl Plugin for SpellPlugin {
             Update,
             (
                 spawn_fire_ball,
-                remove_fireball_on_collision,
+                spawn_frost_bolt,
+                explode_fireball_on_collision,
                 spawn_fireball_explosion,
-            ),
+            )
+                .run_if(in_state(AppState::Playing)),
         )
         .add_event::<FireballExplosionEvent>();
     }
 }
 
 #[derive(Event, PartialEq)]
-pub struct FireballExplosionEvent(Vec2);
+pub struct FireballExplosionEvent {
+    position: Vec2,
+    attacker: Option<Entity>,
+}
 
 pub fn spawn_fire_ball(
     mut commands: Commands,
     windows: Query<&Window>,
     camera_query: Query<(&Camera, &GlobalTransform), With<Camera>>,
-    player_query: Query<&Transform, With<Player>>,
+    player_query: Query<(Entity, &Transform), With<Player>>,
     mut attack_event: EventReader<PlayerAttackEvent>,
+    balance: Res<GameBalance>,
 ) {
     if !attack_event
         .read()
@@ -60,20 +66,23 @@ pub fn spawn_fire_ball(
         .cursor_position()
         .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor))
     {
-        if let Ok(player_transform) = player_query.get_single() {
+        if let Ok((player, player_transform)) = player_query.get_single() {
             let player_position = Vec2::new(
                 player_transform.translation.x,
                 player_transform.translation.y,
             );
             let cursor_position = Vec2::new(world_position.x, world_position.y);
             let direction = (cursor_position - player_position).normalize_or_zero();
+            let radius = balance.fireball.radius;
 
             commands.spawn((
                 Fireball(),
+                Projectile,
                 EntityType::Spell,
+                Attacker(player),
                 RigidBody::Dynamic,
                 Restitution::new(0.),
-                Collider::circle(FIRE_BALL_RADIUS),
+                Collider::circle(radius),
                 CollisionLayers::new(
                     PhysicsLayers::Fireball,
                     [
@@ -82,13 +91,13 @@ pub fn spawn_fire_ball(
                         PhysicsLayers::ClosedTile,
                     ],
                 ),
-                LinearVelocity(Vec2::new(direction.x, direction.y) * FIRE_BALL_SPEED),
+                LinearVelocity(Vec2::new(direction.x, direction.y) * balance.fireball.speed),
                 LinearDamping(0.),
                 LockedAxes::ROTATION_LOCKED,
                 SpriteBundle {
                     sprite: Sprite {
                         color: Color::rgb(1., 0.5, 0.),
-                        custom_size: Some(vec2(FIRE_BALL_RADIUS * 2., FIRE_BALL_RADIUS * 2.)),
+                        custom_size: Some(vec2(radius * 2., radius * 2.)),
                         ..default()
                     },
                     transform: Transform::from_translation(vec3(
@@ -103,17 +112,86 @@ pub fn spawn_fire_ball(
     }
 }
 
-pub fn remove_fireball_on_collision(
-    mut event_writer: EventWriter<EntityDeathEvent>,
+/// Fast bolt that damages the first mob it hits, removed by `hit_with_projectiles`.
+pub fn spawn_frost_bolt(
+    mut commands: Commands,
+    windows: Query<&Window>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<Camera>>,
+    player_query: Query<(Entity, &Transform), With<Player>>,
+    mut attack_event: EventReader<PlayerAttackEvent>,
+    balance: Res<GameBalance>,
+) {
+    if !attack_event
+        .read()
+        .contains(&PlayerAttackEvent(AttackType::Frostbolt))
+    {
+        return;
+    }
+
+    attack_event.clear();
+
+    let window = windows.single();
+    let (camera, camera_transform) = camera_query.single();
+
+    let Some(world_position) = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor))
+    else {
+        return;
+    };
+
+    let Ok((player, player_transform)) = player_query.get_single() else {
+        return;
+    };
+
+    let player_position = player_transform.translation.xy();
+    let direction = (world_position - player_position).normalize_or_zero();
+    let bolt = &balance.frost_bolt;
+
+    commands.spawn((
+        Projectile,
+        EntityType::Spell,
+        Damage(bolt.damage, DamageType::Frost),
+        bolt.status.clone(),
+        Attacker(player),
+        DespawnTimer::from_seconds(bolt.lifetime),
+        RigidBody::Dynamic,
+        Restitution::new(0.),
+        Collider::circle(bolt.radius),
+        CollisionLayers::new(
+            PhysicsLayers::Fireball,
+            [
+                PhysicsLayers::Mob,
+                PhysicsLayers::Wall,
+                PhysicsLayers::ClosedTile,
+            ],
+        ),
+        LinearVelocity(direction * bolt.speed),
+        LinearDamping(0.),
+        LockedAxes::ROTATION_LOCKED,
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgb(0.5, 0.8, 1.),
+                custom_size: Some(vec2(bolt.radius * 2., bolt.radius * 2.)),
+                ..default()
+            },
+            transform: Transform::from_translation(player_position.extend(0.)),
+            ..default()
+        },
+    ));
+}
+
+/// The fireball itself is removed by `hit_with_projectiles`, this only spawns the explosion.
+pub fn explode_fireball_on_collision(
     mut fireball_explosion_event: EventWriter<FireballExplosionEvent>,
-    fireballs: Query<(&CollidingEntities, Entity, &Transform), With<Fireball>>,
+    fireballs: Query<(&CollidingEntities, &Transform, Option<&Attacker>), With<Fireball>>,
 ) {
-    for (collding_entitys, fireball_entity, fireball_transform) in fireballs.iter() {
+    for (collding_entitys, fireball_transform, attacker) in fireballs.iter() {
         if !collding_entitys.0.is_empty() {
-            event_writer.send(EntityDeathEvent(fireball_entity));
-
-            fireball_explosion_event
-                .send(FireballExplosionEvent(fireball_transform.translation.xy()));
+            fireball_explosion_event.send(FireballExplosionEvent {
+                position: fireball_transform.translation.xy(),
+                attacker: attacker.map(|attacker| attacker.0),
+            });
         }
     }
 }
@@ -121,29 +199,45 @@ pub fn remove_fireball_on_collision(
 fn spawn_fireball_explosion(
     mut commands: Commands,
     mut attack_event: EventReader<FireballExplosionEvent>,
+    balance: Res<GameBalance>,
 ) {
+    let fireball = &balance.fireball;
+
     for event in attack_event.read() {
-        let fireball_pos = event.0;
+        let fireball_pos = event.position;
 
-        commands.spawn((
+        let mut explosion = commands.spawn((
             LongTimeAttack {
                 damaged_entities: vec![],
+                from: EntityType::Player,
+            },
+            Damage(fireball.damage, DamageType::Fire),
+            fireball.status.clone(),
+            Knockback {
+                strength: fireball.knockback,
+                origin: KnockbackOrigin::Center,
             },
-            Damage(FIRE_BALL_DAMAGE as f64),
             SpriteBundle {
                 sprite: Sprite {
                     color: Color::rgb(0., 1., 0.),
-                    custom_size: Some(vec2(50., 50.)),
+                    custom_size: Some(vec2(
+                        fireball.explosion_radius * 2.,
+                        fireball.explosion_radius * 2.,
+                    )),
                     ..default()
                 },
                 transform: Transform::from_xyz(fireball_pos.x, fireball_pos.y, 0.),
                 ..default()
             },
-            DespawnTimer::from_seconds(0.2),
+            DespawnTimer::from_seconds(fireball.explosion_seconds),
             Sensor,
             CollisionLayers::new([PhysicsLayers::Fireball], [PhysicsLayers::Mob]),
-            Collider::circle(25.),
+            Collider::circle(fireball.explosion_radius),
             RigidBody::Static,
         ));
+
+        if let Some(attacker) = event.attacker {
+            explosion.insert(Attacker(attacker));
+        }
     }
 }
@@ -1,9 +1,12 @@
-use crate::entities::data::{Damage, DespawnTimer, Player, PLAYER_DAMAGE, PLAYER_RADIUS};
+use crate::balance::GameBalance;
+use crate::entities::data::{
+    Attacker, Damage, DamageType, DespawnTimer, EntityType, Knockback, KnockbackOrigin, Player,
+};
 
 use crate::entities::player::attacks::PlayerAttackEvent;
 
 use crate::ui::AttackType;
-use crate::PhysicsLayers;
+use crate::{AppState, PhysicsLayers};
 use bevy::app::{App, Plugin, Update};
 
 use crate::entities::longtime_attack::LongTimeAttack;
@@ -16,7 +19,10 @@ pub struct MeleePlugin;
 
 impl Plugin for MeleePlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, spawn_player_attack);
+        app.add_systems(
+            Update,
+            spawn_player_attack.run_if(in_state(AppState::Playing)),
+        );
     }
 }
 
@@ -24,8 +30,9 @@ fn spawn_player_attack(
     windows: Query<&Window>,
     camera_query: Query<(&Camera, &GlobalTransform), With<Camera>>,
     mut commands: Commands,
-    player_query: Query<&Transform, With<Player>>,
+    player_query: Query<(Entity, &Transform), With<Player>>,
     mut attack_event: EventReader<PlayerAttackEvent>,
+    balance: Res<GameBalance>,
 ) {
     if !attack_event
         .read()
@@ -36,7 +43,7 @@ fn spawn_player_attack(
 
     attack_event.clear();
 
-    let player_transform = player_query.single();
+    let (player, player_transform) = player_query.single();
     let window = windows.single();
     let (camera, camera_transform) = camera_query.single();
 
@@ -49,13 +56,19 @@ fn spawn_player_attack(
 
         let dir = (world_pos - player_pos).normalize_or_zero();
 
-        let collider_pos = (dir * (PLAYER_RADIUS * 2.)).xy() + player_pos;
+        let collider_pos = (dir * (balance.player.radius * 2.)).xy() + player_pos;
 
         commands.spawn((
             LongTimeAttack {
                 damaged_entities: vec![],
+                from: EntityType::Player,
+            },
+            Damage(balance.melee.damage, DamageType::Physical),
+            Attacker(player),
+            Knockback {
+                strength: balance.melee.knockback,
+                origin: KnockbackOrigin::Attacker,
             },
-            Damage(PLAYER_DAMAGE),
             Rotation::from_radians(Vec2::X.angle_between(dir)),
             SpriteBundle {
                 sprite: Sprite {
@@ -66,9 +79,12 @@ fn spawn_player_attack(
                 transform: Transform::from_xyz(collider_pos.x, collider_pos.y, 0.),
                 ..default()
             },
-            DespawnTimer::from_seconds(0.2),
+            DespawnTimer::from_seconds(balance.melee.seconds),
             Sensor,
-            CollisionLayers::new([PhysicsLayers::Player], [PhysicsLayers::Mob]),
+            CollisionLayers::new(
+                [PhysicsLayers::Player],
+                [PhysicsLayers::Mob, PhysicsLayers::Tree],
+            ),
             Collider::capsule(18., 6.),
             RigidBody::Static,
         ));
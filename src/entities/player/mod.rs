@@ -1,15 +1,19 @@
+use crate::balance::GameBalance;
 use crate::entities::data::{
-    AttackableFrom, Damage, EntityType, Health, Player, PlayerAttackCoolDown, MAX_PLAYER_HEALTH,
-    PLAYER_RADIUS, PLAYER_SPEED,
+    AttackableFrom, BaseColor, Damage, DamageType, Dead, EntityType, Health, Player,
+    PlayerAttackCoolDown,
 };
 use crate::entities::event::PlayerMoveEvent;
-use crate::PhysicsLayers;
+use crate::entities::status::{speed_multiplier, StatusEffects};
+use crate::{AppState, PhysicsLayers};
 use bevy::math::{vec2, vec3};
 use bevy::prelude::*;
 use bevy_xpbd_2d::prelude::*;
 use std::ops::Mul;
+use std::time::Duration;
 
 pub mod attacks;
+pub mod death;
 pub mod fireball;
 pub mod melee;
 
@@ -17,34 +21,46 @@ pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, player_setup)
-            .add_systems(Update, (handle_keyboard_input, move_player).chain());
+        app.add_systems(OnExit(AppState::Menu), player_setup)
+            .add_systems(
+                Update,
+                (handle_keyboard_input, move_player)
+                    .chain()
+                    .run_if(in_state(AppState::Playing)),
+            )
+            .add_systems(
+                Update,
+                update_player_stats.run_if(resource_exists_and_changed::<GameBalance>),
+            );
     }
 }
 
-pub fn player_setup(mut commands: Commands) {
+pub fn player_setup(mut commands: Commands, balance: Res<GameBalance>) {
+    let radius = balance.player.radius;
+
     commands.spawn((
         Player,
         EntityType::Player,
         //add here all layers which can make damage to a player
-        AttackableFrom(vec![EntityType::Mob]),
-        Damage(1.),
-        Health(MAX_PLAYER_HEALTH),
-        PlayerAttackCoolDown::default(),
+        AttackableFrom(vec![EntityType::Mob, EntityType::EnemyProjectile]),
+        Damage(1., DamageType::Physical),
+        Health(balance.player.max_health),
+        BaseColor(Color::rgb(0.25, 0.25, 0.75)),
+        PlayerAttackCoolDown::new(balance.player.attack_cooldown),
         RigidBody::Dynamic,
         Restitution::new(0.),
-        Collider::circle(PLAYER_RADIUS),
+        Collider::circle(radius),
         CollisionLayers::new(
             [PhysicsLayers::Player, PhysicsLayers::Entity],
             LayerMask::ALL,
         ),
         LinearVelocity(vec2(0., 0.)),
-        LinearDamping(20.),
+        LinearDamping(balance.player.linear_damping),
         LockedAxes::ROTATION_LOCKED,
         SpriteBundle {
             sprite: Sprite {
                 color: Color::rgb(0.25, 0.25, 0.75),
-                custom_size: Some(vec2(PLAYER_RADIUS * 2., PLAYER_RADIUS * 2.)),
+                custom_size: Some(vec2(radius * 2., radius * 2.)),
                 ..default()
             },
             transform: Transform::from_translation(vec3(0., 0., 0.)),
@@ -84,16 +100,34 @@ pub fn handle_keyboard_input(
 
 pub fn move_player(
     mut player_move_events: EventReader<PlayerMoveEvent>,
-    mut player: Query<&mut LinearVelocity, With<Player>>,
+    mut player: Query<(&mut LinearVelocity, Option<&StatusEffects>), (With<Player>, Without<Dead>)>,
+    balance: Res<GameBalance>,
 ) {
-    let Ok(mut velocity) = player.get_single_mut() else {
+    let Ok((mut velocity, status)) = player.get_single_mut() else {
         return;
     };
 
     for player_move_event in player_move_events.read() {
-        let direction = player_move_event.0.normalize_or_zero().mul(PLAYER_SPEED);
+        let direction = player_move_event
+            .0
+            .normalize_or_zero()
+            .mul(balance.player.speed * speed_multiplier(status, &balance.status));
 
         velocity.x = direction.x;
         velocity.y = direction.y;
     }
 }
+
+/// Applies a reloaded balance to the existing player, the speed is read every frame anyway.
+/// The current health stays as it is, only the maximum changes.
+fn update_player_stats(
+    mut player_query: Query<(&mut PlayerAttackCoolDown, &mut LinearDamping), With<Player>>,
+    balance: Res<GameBalance>,
+) {
+    for (mut attack_timer, mut damping) in player_query.iter_mut() {
+        attack_timer
+            .0
+            .set_duration(Duration::from_secs_f32(balance.player.attack_cooldown));
+        damping.0 = balance.player.linear_damping;
+    }
+}
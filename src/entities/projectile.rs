@@ -0,0 +1,149 @@
+use crate::balance::GameBalance;
+use crate::entities::ai::{MobAi, MobState, MobStateChanged};
+use crate::entities::data::{
+    AttackableFrom, Attacker, Damage, DespawnTimer, EntityType, Health, Projectile,
+};
+use crate::entities::event::{EntityDamageEvent, EntityDeathEvent};
+use crate::entities::mob_kind::ProjectileStats;
+use crate::entities::status::StatusOnHit;
+use crate::{AppState, PhysicsLayers};
+use bevy::app::{App, Plugin, Update};
+use bevy::math::vec2;
+use bevy::prelude::*;
+use bevy_xpbd_2d::prelude::*;
+
+pub struct ProjectilePlugin;
+
+impl Plugin for ProjectilePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (fire_mob_projectiles, hit_with_projectiles).run_if(in_state(AppState::Playing)),
+        );
+    }
+}
+
+pub fn spawn_enemy_projectile(
+    commands: &mut Commands,
+    stats: &ProjectileStats,
+    position: Vec2,
+    direction: Vec2,
+    damage: Damage,
+    attacker: Entity,
+    lifetime: f32,
+) {
+    commands.spawn((
+        Projectile,
+        EntityType::EnemyProjectile,
+        damage,
+        Attacker(attacker),
+        DespawnTimer::from_seconds(lifetime),
+        RigidBody::Dynamic,
+        Restitution::new(0.),
+        Collider::circle(stats.radius),
+        // mobs are not in the filter, so enemy projectiles fly through them
+        CollisionLayers::new(
+            PhysicsLayers::EnemyProjectile,
+            [
+                PhysicsLayers::Player,
+                PhysicsLayers::Wall,
+                PhysicsLayers::Tree,
+                PhysicsLayers::ClosedTile,
+            ],
+        ),
+        LinearVelocity(direction * stats.speed),
+        LinearDamping(0.),
+        LockedAxes::ROTATION_LOCKED,
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgb(stats.color.0, stats.color.1, stats.color.2),
+                custom_size: Some(vec2(stats.radius * 2., stats.radius * 2.)),
+                ..default()
+            },
+            transform: Transform::from_translation(position.extend(0.)),
+            ..default()
+        },
+    ));
+}
+
+/// Ranged mobs fire once their wind-up is over and they enter `MobState::Attack`.
+fn fire_mob_projectiles(
+    mut commands: Commands,
+    mut state_changed: EventReader<MobStateChanged>,
+    mobs: Query<(&Transform, &MobAi, &Damage, &ProjectileStats)>,
+    balance: Res<GameBalance>,
+) {
+    for event in state_changed.read() {
+        if event.to != MobState::Attack {
+            continue;
+        }
+
+        let Ok((transform, ai, damage, stats)) = mobs.get(event.entity) else {
+            continue;
+        };
+
+        spawn_enemy_projectile(
+            &mut commands,
+            stats,
+            transform.translation.xy(),
+            ai.attack_direction,
+            Damage(damage.0, damage.1),
+            event.entity,
+            balance.mobs.projectile_lifetime,
+        );
+    }
+}
+
+/// Removes projectiles once they hit something, projectiles with `Damage` hurt what they hit
+/// if it can be attacked by them.
+pub fn hit_with_projectiles(
+    mut death_events: EventWriter<EntityDeathEvent>,
+    mut damage_events: EventWriter<EntityDamageEvent>,
+    projectiles: Query<
+        (
+            Entity,
+            &CollidingEntities,
+            &EntityType,
+            Option<&Damage>,
+            Option<&Attacker>,
+            Option<&StatusOnHit>,
+        ),
+        With<Projectile>,
+    >,
+    attackable: Query<&AttackableFrom, With<Health>>,
+) {
+    for (projectile, colliding_entities, entity_type, damage, attacker, status) in
+        projectiles.iter()
+    {
+        if colliding_entities.0.is_empty() {
+            continue;
+        }
+
+        let attacker = attacker.map_or(projectile, |attacker| attacker.0);
+
+        if let Some(damage) = damage {
+            for hit_entity in colliding_entities.0.iter() {
+                if attackable
+                    .get(*hit_entity)
+                    .is_ok_and(|attackable_from| attackable_from.0.contains(entity_type))
+                {
+                    damage_events.send(EntityDamageEvent {
+                        entity: *hit_entity,
+                        attacker,
+                        source: Some(projectile),
+                        damage: damage.0,
+                        damage_type: damage.1,
+                        knockback: None,
+                        status: status.cloned(),
+                    });
+                }
+            }
+        }
+
+        death_events.send(EntityDeathEvent {
+            entity: projectile,
+            attacker: None,
+            overkill: 0.,
+        });
+    }
+}
@@ -0,0 +1,183 @@
+use crate::balance::{GameBalance, SpawnerBalance};
+use crate::entities::data::{Mob, Player};
+use crate::entities::mob::spawn_mob;
+use crate::entities::mob_kind::{pick_mob_kind, MobKinds};
+use crate::world::biome::{Biome, BiomeMap};
+use crate::world::grid::{TileGrid, TileState};
+use crate::world::{SUB_TILE_SIZE, TILE_SIZE};
+use crate::AppState;
+use bevy::app::{App, Plugin, Update};
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+use rand::{thread_rng, Rng};
+
+pub struct SpawnerPlugin;
+
+impl Plugin for SpawnerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MobSpawner>()
+            .add_systems(OnExit(AppState::Menu), restart_spawner)
+            .add_systems(
+                Update,
+                spawn_frontier_mobs.run_if(in_state(AppState::Playing)),
+            );
+    }
+}
+
+static SPAWN_POINT_ATTEMPTS: usize = 8;
+
+#[derive(Resource, Default)]
+pub struct MobSpawner {
+    /// seconds since the run started
+    pub elapsed: f32,
+    /// fractional mobs carried over between frames
+    pub budget: f32,
+}
+
+impl MobSpawner {
+    pub fn spawn_rate(&self, open_tiles: usize, balance: &SpawnerBalance) -> f32 {
+        let growth = 1. + self.elapsed / 60. * balance.rate_growth_per_minute;
+
+        (balance.base_rate + balance.rate_per_open_tile * open_tiles as f32) * growth
+    }
+}
+
+/// Edge between a closed tile and an open tile, mobs crawl out of the closed tile across it.
+#[derive(Clone, Copy)]
+pub struct FrontierEdge {
+    pub closed: IVec2,
+    pub open: IVec2,
+}
+
+impl FrontierEdge {
+    /// Random point just inside the open tile along the shared edge.
+    pub fn random_point(&self, rng: &mut impl Rng) -> Vec2 {
+        let direction = (self.open - self.closed).as_vec2();
+        let along = direction.perp() * rng.gen_range(-0.5..0.5) * (TILE_SIZE - SUB_TILE_SIZE);
+        let edge_center = TileGrid::grid_to_world(self.closed) + direction * TILE_SIZE / 2.;
+
+        edge_center + direction * SUB_TILE_SIZE + along
+    }
+}
+
+pub struct SpawnPoint {
+    pub position: Vec2,
+    /// biome of the closed tile the mob crawls out of
+    pub biome: Biome,
+}
+
+pub fn frontier_edges(grid: &TileGrid) -> Vec<FrontierEdge> {
+    grid.iter()
+        .filter(|(_, tile)| tile.state == TileState::Closed)
+        .flat_map(|(closed, _)| {
+            TileGrid::edge_neighbours(closed)
+                .filter(|open| grid.state(*open) == Some(TileState::Open))
+                .map(move |open| FrontierEdge { closed, open })
+        })
+        .collect()
+}
+
+/// Picks a frontier edge weighted by the biome of the closed tile and a point on it that is far
+/// enough away from the player.
+pub fn find_spawn_point(
+    edges: &[FrontierEdge],
+    biome_map: &BiomeMap,
+    player_position: Vec2,
+    min_distance: f32,
+    rng: &mut impl Rng,
+) -> Option<SpawnPoint> {
+    for _ in 0..SPAWN_POINT_ATTEMPTS {
+        let Ok(edge) = edges.choose_weighted(rng, |edge| {
+            biome_map
+                .tile_biome(edge.closed)
+                .definition()
+                .mob_spawn_rate
+        }) else {
+            return None;
+        };
+
+        let point = edge.random_point(rng);
+
+        if point.distance(player_position) >= min_distance {
+            return Some(SpawnPoint {
+                position: point,
+                biome: biome_map.tile_biome(edge.closed),
+            });
+        }
+    }
+
+    None
+}
+
+/// The spawn rate grows with the run time, so it starts over with a new run.
+fn restart_spawner(mut spawner: ResMut<MobSpawner>) {
+    *spawner = MobSpawner::default();
+}
+
+pub fn spawn_frontier_mobs(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut spawner: ResMut<MobSpawner>,
+    grid: Res<TileGrid>,
+    biome_map: Res<BiomeMap>,
+    mob_kinds: MobKinds,
+    mobs: Query<(), With<Mob>>,
+    player_query: Query<&Transform, With<Player>>,
+    balance: Res<GameBalance>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+
+    if !mob_kinds.is_loaded() {
+        return;
+    }
+
+    let open_tiles = grid
+        .iter()
+        .filter(|(_, tile)| tile.state == TileState::Open)
+        .count();
+
+    spawner.elapsed += time.delta_seconds();
+    spawner.budget += spawner.spawn_rate(open_tiles, &balance.spawner) * time.delta_seconds();
+
+    if spawner.budget < 1. {
+        return;
+    }
+
+    let mut live_mobs = mobs.iter().count();
+
+    if live_mobs >= balance.spawner.max_live_mobs {
+        // don't bank spawns while the cap is reached
+        spawner.budget = spawner.budget.min(1.);
+        return;
+    }
+
+    let edges = frontier_edges(&grid);
+    let mut rng = thread_rng();
+
+    while spawner.budget >= 1. && live_mobs < balance.spawner.max_live_mobs {
+        spawner.budget -= 1.;
+
+        let Some(spawn_point) = find_spawn_point(
+            &edges,
+            &biome_map,
+            player_transform.translation.xy(),
+            balance.spawner.min_distance,
+            &mut rng,
+        ) else {
+            continue;
+        };
+
+        let Some(kind) = pick_mob_kind(spawn_point.biome.definition().mobs, &mut rng) else {
+            continue;
+        };
+
+        let Some(stats) = mob_kinds.stats(kind) else {
+            continue;
+        };
+
+        spawn_mob(&mut commands, kind, stats, spawn_point.position, &balance);
+        live_mobs += 1;
+    }
+}
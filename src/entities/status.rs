@@ -0,0 +1,260 @@
+use crate::balance::{GameBalance, StatusBalance};
+use crate::entities::data::{DamageType, Health, Resistances};
+use crate::entities::entity::deal_damage;
+use crate::entities::event::EntityDamageEvent;
+use crate::AppState;
+use bevy::app::{App, Plugin, Update};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub struct StatusPlugin;
+
+impl Plugin for StatusPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ApplyStatusEvent>().add_systems(
+            Update,
+            (apply_status_effects.after(deal_damage), tick_status_effects)
+                .chain()
+                .run_if(in_state(AppState::Playing)),
+        );
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum StatusKind {
+    /// fire damage over time
+    Burning,
+    /// slowed down, turns into `Frozen` at maximum stacks
+    Chilled,
+    Frozen,
+    Stunned,
+}
+
+/// What a status kind does, its numbers are in `StatusBalance`.
+pub struct StatusDefinition {
+    pub damage_type: DamageType,
+    /// can't move, attack or think while this is active
+    pub blocks_actions: bool,
+    pub tint: Color,
+}
+
+impl StatusKind {
+    pub fn definition(&self) -> StatusDefinition {
+        match self {
+            StatusKind::Burning => StatusDefinition {
+                damage_type: DamageType::Fire,
+                blocks_actions: false,
+                tint: Color::rgb(1., 0.45, 0.1),
+            },
+            StatusKind::Chilled => StatusDefinition {
+                damage_type: DamageType::Frost,
+                blocks_actions: false,
+                tint: Color::rgb(0.6, 0.8, 1.),
+            },
+            StatusKind::Frozen => StatusDefinition {
+                damage_type: DamageType::Frost,
+                blocks_actions: true,
+                tint: Color::rgb(0.85, 0.95, 1.),
+            },
+            StatusKind::Stunned => StatusDefinition {
+                damage_type: DamageType::Physical,
+                blocks_actions: true,
+                tint: Color::rgb(1., 0.95, 0.4),
+            },
+        }
+    }
+}
+
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    pub stacks: u32,
+    pub duration: Timer,
+    pub tick: Timer,
+    /// credited with the damage over time
+    pub source: Entity,
+}
+
+/// Every status effect currently active on an entity, removed once the last one expired.
+#[derive(Component, Default)]
+pub struct StatusEffects(pub Vec<StatusEffect>);
+
+impl StatusEffects {
+    pub fn speed_multiplier(&self, balance: &StatusBalance) -> f32 {
+        self.0
+            .iter()
+            .map(|effect| {
+                balance
+                    .slow_per_stack(effect.kind)
+                    .powi(effect.stacks as i32)
+            })
+            .product()
+    }
+
+    pub fn blocks_actions(&self) -> bool {
+        self.0
+            .iter()
+            .any(|effect| effect.kind.definition().blocks_actions)
+    }
+
+    /// Tint of the most recently applied effect.
+    pub fn tint(&self) -> Option<Color> {
+        self.0.last().map(|effect| effect.kind.definition().tint)
+    }
+}
+
+/// Shorthand for optional `StatusEffects` query items.
+pub fn speed_multiplier(effects: Option<&StatusEffects>, balance: &StatusBalance) -> f32 {
+    effects.map_or(1., |effects| effects.speed_multiplier(balance))
+}
+
+pub fn blocks_actions(effects: Option<&StatusEffects>) -> bool {
+    effects.is_some_and(StatusEffects::blocks_actions)
+}
+
+#[derive(Event, Clone)]
+pub struct ApplyStatusEvent {
+    pub entity: Entity,
+    pub source: Entity,
+    pub kind: StatusKind,
+    pub stacks: u32,
+    pub seconds: f32,
+}
+
+/// Status applied to everything an attack damages, e.g. burning from fireball explosions.
+#[derive(Component, Clone, Debug, Serialize, Deserialize)]
+pub struct StatusOnHit {
+    pub kind: StatusKind,
+    pub stacks: u32,
+    pub seconds: f32,
+}
+
+impl StatusOnHit {
+    pub fn event(&self, entity: Entity, source: Entity) -> ApplyStatusEvent {
+        ApplyStatusEvent {
+            entity,
+            source,
+            kind: self.kind,
+            stacks: self.stacks,
+            seconds: self.seconds,
+        }
+    }
+}
+
+fn apply_status_effects(
+    mut commands: Commands,
+    mut apply_events: EventReader<ApplyStatusEvent>,
+    mut targets: Query<(Option<&mut StatusEffects>, Option<&Resistances>), With<Health>>,
+    balance: Res<GameBalance>,
+) {
+    for event in apply_events.read() {
+        let Ok((effects, resistances)) = targets.get_mut(event.entity) else {
+            continue;
+        };
+
+        let definition = event.kind.definition();
+
+        // immune to the damage type means immune to its effects as well
+        if resistances
+            .is_some_and(|resistances| resistances.multiplier(definition.damage_type) <= 0.)
+        {
+            continue;
+        }
+
+        let Some(mut effects) = effects else {
+            let mut effects = StatusEffects::default();
+            add_status(&mut effects, event, &balance.status);
+            commands.entity(event.entity).insert(effects);
+            continue;
+        };
+
+        add_status(&mut effects, event, &balance.status);
+    }
+}
+
+fn add_status(effects: &mut StatusEffects, event: &ApplyStatusEvent, balance: &StatusBalance) {
+    let max_stacks = balance.max_stacks(event.kind);
+    let tick_seconds = balance
+        .damage_over_time(event.kind)
+        .map_or(0., |(tick_seconds, _)| tick_seconds);
+
+    let effect = match effects
+        .0
+        .iter()
+        .position(|effect| effect.kind == event.kind)
+    {
+        // move it to the end so its tint shows
+        Some(index) => {
+            let mut effect = effects.0.remove(index);
+            effect.stacks = (effect.stacks + event.stacks).min(max_stacks);
+            effect.duration = Timer::from_seconds(
+                effect.duration.remaining_secs().max(event.seconds),
+                TimerMode::Once,
+            );
+            effect.source = event.source;
+            effect
+        }
+        None => StatusEffect {
+            kind: event.kind,
+            stacks: event.stacks.min(max_stacks),
+            duration: Timer::from_seconds(event.seconds, TimerMode::Once),
+            tick: Timer::from_seconds(tick_seconds, TimerMode::Repeating),
+            source: event.source,
+        },
+    };
+
+    if effect.kind == StatusKind::Chilled && effect.stacks >= max_stacks {
+        effects.0.retain(|effect| effect.kind != StatusKind::Frozen);
+        effects.0.push(StatusEffect {
+            kind: StatusKind::Frozen,
+            stacks: 1,
+            duration: Timer::from_seconds(balance.chilled.freeze_seconds, TimerMode::Once),
+            tick: Timer::from_seconds(0., TimerMode::Repeating),
+            source: effect.source,
+        });
+        return;
+    }
+
+    effects.0.push(effect);
+}
+
+fn tick_status_effects(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut entities: Query<(Entity, &mut StatusEffects)>,
+    mut damage_events: EventWriter<EntityDamageEvent>,
+    balance: Res<GameBalance>,
+) {
+    for (entity, mut effects) in entities.iter_mut() {
+        for effect in effects.0.iter_mut() {
+            effect.duration.tick(time.delta());
+
+            let Some((_, damage_per_stack)) = balance.status.damage_over_time(effect.kind) else {
+                continue;
+            };
+
+            if damage_per_stack <= 0. {
+                continue;
+            }
+
+            let ticks = effect.tick.tick(time.delta()).times_finished_this_tick();
+
+            for _ in 0..ticks {
+                damage_events.send(EntityDamageEvent {
+                    entity,
+                    attacker: effect.source,
+                    source: None,
+                    damage: damage_per_stack * effect.stacks as f64,
+                    damage_type: effect.kind.definition().damage_type,
+                    knockback: None,
+                    status: None,
+                });
+            }
+        }
+
+        effects.0.retain(|effect| !effect.duration.finished());
+
+        if effects.0.is_empty() {
+            commands.entity(entity).remove::<StatusEffects>();
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use crate::entities::data::Mob;
+use crate::AppState;
+use bevy::app::{App, Plugin, Update};
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy_xpbd_2d::prelude::LinearVelocity;
+use rand::{thread_rng, Rng};
+use std::f32::consts::TAU;
+
+pub struct SteeringPlugin;
+
+impl Plugin for SteeringPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MobSpatialHash>().add_systems(
+            Update,
+            build_mob_spatial_hash.run_if(in_state(AppState::Playing)),
+        );
+    }
+}
+
+/// Mobs closer than this influence each other's steering, also the cell size of the spatial hash.
+pub static NEIGHBOUR_RADIUS: f32 = 24.;
+pub static SEPARATION_WEIGHT: f32 = 1.5;
+pub static ALIGNMENT_WEIGHT: f32 = 0.3;
+/// Surrounding mobs start to circle towards their side of the player within this distance.
+pub static SURROUND_DISTANCE: f32 = 96.;
+pub static SURROUND_WEIGHT: f32 = 0.8;
+
+/// Mobs bucketed into a grid each frame, so neighbour lookups stay cheap with hundreds of mobs.
+#[derive(Resource, Default)]
+pub struct MobSpatialHash {
+    cells: HashMap<IVec2, Vec<(Entity, Vec2, Vec2)>>,
+}
+
+impl MobSpatialHash {
+    fn cell(position: Vec2) -> IVec2 {
+        (position / NEIGHBOUR_RADIUS).floor().as_ivec2()
+    }
+
+    /// Position and velocity of every other mob within `NEIGHBOUR_RADIUS`.
+    pub fn neighbours(
+        &self,
+        entity: Entity,
+        position: Vec2,
+    ) -> impl Iterator<Item = (Vec2, Vec2)> + '_ {
+        let cell = MobSpatialHash::cell(position);
+
+        (-1..=1)
+            .flat_map(move |x| (-1..=1).map(move |y| cell + IVec2::new(x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .filter(move |(other, other_position, _)| {
+                *other != entity && other_position.distance(position) < NEIGHBOUR_RADIUS
+            })
+            .map(|(_, other_position, velocity)| (*other_position, *velocity))
+    }
+
+    /// Separation from and alignment with the neighbouring mobs.
+    pub fn flocking(&self, entity: Entity, position: Vec2) -> Vec2 {
+        let mut separation = Vec2::ZERO;
+        let mut velocity_sum = Vec2::ZERO;
+
+        for (other_position, velocity) in self.neighbours(entity, position) {
+            let away = position - other_position;
+            let closeness = 1. - away.length() / NEIGHBOUR_RADIUS;
+
+            // mobs on the exact same spot push apart in a random direction
+            let away = away
+                .try_normalize()
+                .unwrap_or_else(|| Vec2::from_angle(thread_rng().gen_range(0. ..TAU)));
+
+            separation += away * closeness;
+            velocity_sum += velocity;
+        }
+
+        separation * SEPARATION_WEIGHT + velocity_sum.normalize_or_zero() * ALIGNMENT_WEIGHT
+    }
+}
+
+/// Makes a chasing mob approach the player from its own side instead of the shortest way.
+#[derive(Component)]
+pub struct Surround {
+    /// direction from the player to the spot this mob wants to attack from
+    pub side: Vec2,
+}
+
+impl Surround {
+    pub fn random() -> Self {
+        Surround {
+            side: Vec2::from_angle(thread_rng().gen_range(0. ..TAU)),
+        }
+    }
+
+    /// Sideways push around the player towards `side`, zero while still far away.
+    pub fn steer(&self, to_player: Vec2) -> Vec2 {
+        let distance = to_player.length();
+
+        if distance > SURROUND_DISTANCE || distance == 0. {
+            return Vec2::ZERO;
+        }
+
+        let current_side = -to_player / distance;
+        let tangent = to_player.perp() / distance;
+        let turn = current_side.perp_dot(self.side);
+
+        // stronger the further off the side and the closer to the player
+        tangent * -turn * (1. - distance / SURROUND_DISTANCE) * SURROUND_WEIGHT
+    }
+}
+
+pub fn build_mob_spatial_hash(
+    mut spatial_hash: ResMut<MobSpatialHash>,
+    mobs: Query<(Entity, &Transform, &LinearVelocity), With<Mob>>,
+) {
+    spatial_hash.cells.clear();
+
+    for (entity, transform, velocity) in mobs.iter() {
+        let position = transform.translation.xy();
+
+        spatial_hash
+            .cells
+            .entry(MobSpatialHash::cell(position))
+            .or_default()
+            .push((entity, position, velocity.0));
+    }
+}
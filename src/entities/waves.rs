@@ -0,0 +1,490 @@
+use crate::balance::GameBalance;
+use crate::entities::ai::MobAi;
+use crate::entities::data::{Damage, Health, Player};
+use crate::entities::entity::deal_damage;
+use crate::entities::event::EntityDeathEvent;
+use crate::entities::mob::spawn_mob;
+use crate::entities::mob_kind::{pick_mob_kind, MobKind, MobKinds};
+use crate::entities::spawner::{find_spawn_point, frontier_edges};
+use crate::ron_asset::RonAssetLoader;
+use crate::world::biome::BiomeMap;
+use crate::world::grid::TileGrid;
+use crate::AppState;
+use bevy::app::{App, Plugin, Startup, Update};
+use bevy::prelude::*;
+use iter_tools::Itertools;
+use rand::thread_rng;
+use serde::Deserialize;
+use std::fmt::{Display, Formatter};
+
+pub struct WavePlugin;
+
+impl Plugin for WavePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<WaveTable>()
+            .register_asset_loader(RonAssetLoader::<WaveTable>::new(&["waves.ron"]))
+            .add_event::<WaveStarted>()
+            .add_event::<WaveCleared>()
+            .add_systems(Startup, setup_wave_director)
+            .add_systems(Update, apply_wave_table)
+            .add_systems(OnExit(AppState::Menu), restart_waves)
+            .add_systems(
+                Update,
+                (count_wave_kills.after(deal_damage), run_wave_director)
+                    .chain()
+                    .run_if(in_state(AppState::Playing)),
+            );
+    }
+}
+
+pub static WAVE_TABLE_PATH: &str = "waves.ron";
+
+/// Wave definitions, loaded from `assets/waves.ron`.
+/// Inserted as a resource once the file is loaded and valid, waves don't start before that.
+#[derive(Asset, Resource, TypePath, Deserialize, Clone)]
+pub struct WaveTable {
+    /// seconds before the first wave starts
+    pub first_wave_delay: f32,
+    /// seconds between clearing a wave and the next one
+    pub calm_seconds: f32,
+    pub waves: Vec<WaveDefinition>,
+    /// applied once for every wave after the last defined one
+    pub endless_scaling: WaveScaling,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct WaveDefinition {
+    pub mob_count: u32,
+    /// seconds between two spawned mobs
+    pub spawn_interval: f32,
+    pub health_multiplier: f64,
+    pub damage_multiplier: f64,
+    /// mob kinds of this wave with their relative weights,
+    /// empty to use the spawn table of the biome the mob emerges from
+    #[serde(default)]
+    pub mobs: Vec<(MobKind, f32)>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct WaveScaling {
+    pub mob_count: f32,
+    pub health: f64,
+    pub damage: f64,
+}
+
+#[derive(Debug)]
+pub enum WaveTableError {
+    NotPositive { field: String, value: f64 },
+    Negative { field: String, value: f64 },
+    NoWaves,
+}
+
+impl Display for WaveTableError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WaveTableError::NotPositive { field, value } => {
+                write!(f, "{field} has to be greater than 0, got {value}")
+            }
+            WaveTableError::Negative { field, value } => {
+                write!(f, "{field} can't be negative, got {value}")
+            }
+            WaveTableError::NoWaves => write!(f, "waves needs at least one wave"),
+        }
+    }
+}
+
+impl std::error::Error for WaveTableError {}
+
+impl WaveTable {
+    /// Every problem in the file at once, so they can all be fixed in one go.
+    pub fn validate(&self) -> Vec<WaveTableError> {
+        let mut errors = vec![];
+
+        if self.waves.is_empty() {
+            errors.push(WaveTableError::NoWaves);
+        }
+
+        let mut positive = vec![];
+
+        let mut not_negative = vec![
+            ("first_wave_delay".to_string(), self.first_wave_delay as f64),
+            ("calm_seconds".to_string(), self.calm_seconds as f64),
+            (
+                "endless_scaling.mob_count".to_string(),
+                self.endless_scaling.mob_count as f64,
+            ),
+            (
+                "endless_scaling.health".to_string(),
+                self.endless_scaling.health,
+            ),
+            (
+                "endless_scaling.damage".to_string(),
+                self.endless_scaling.damage,
+            ),
+        ];
+
+        for (index, wave) in self.waves.iter().enumerate() {
+            let field = |name: &str| format!("waves.{index}.{name}");
+
+            positive.push((field("spawn_interval"), wave.spawn_interval as f64));
+            positive.push((field("health_multiplier"), wave.health_multiplier));
+            not_negative.push((field("damage_multiplier"), wave.damage_multiplier));
+
+            for (kind, weight) in &wave.mobs {
+                not_negative.push((field(&format!("mobs.{kind:?}")), *weight as f64));
+            }
+        }
+
+        for (field, value) in positive {
+            if value.is_nan() || value <= 0. {
+                errors.push(WaveTableError::NotPositive { field, value });
+            }
+        }
+
+        for (field, value) in not_negative {
+            if value.is_nan() || value < 0. {
+                errors.push(WaveTableError::Negative { field, value });
+            }
+        }
+
+        errors
+    }
+
+    /// Definition of the wave with the given number, starting at 1.
+    pub fn wave(&self, number: u32) -> Option<WaveDefinition> {
+        let index = number.checked_sub(1)? as usize;
+
+        if let Some(wave) = self.waves.get(index) {
+            return Some(wave.clone());
+        }
+
+        let last = self.waves.last()?;
+        let extra_waves = (index + 1 - self.waves.len()) as i32;
+
+        Some(WaveDefinition {
+            mob_count: (last.mob_count as f32 * self.endless_scaling.mob_count.powi(extra_waves))
+                .round() as u32,
+            spawn_interval: last.spawn_interval,
+            health_multiplier: last.health_multiplier
+                * self.endless_scaling.health.powi(extra_waves),
+            damage_multiplier: last.damage_multiplier
+                * self.endless_scaling.damage.powi(extra_waves),
+            mobs: last.mobs.clone(),
+        })
+    }
+}
+
+#[derive(Event)]
+pub struct WaveStarted {
+    pub wave: u32,
+    pub mob_count: u32,
+}
+
+#[derive(Event)]
+pub struct WaveCleared {
+    pub wave: u32,
+}
+
+/// Marks mobs spawned by the director so kills can be credited to their wave.
+#[derive(Component)]
+pub struct WaveMob {
+    pub wave: u32,
+    /// kept so reloaded mob stats can be scaled again, see `update_mob_stats`
+    pub damage_multiplier: f64,
+}
+
+pub enum WavePhase {
+    /// waiting for the wave table to load
+    Loading,
+    Calm(Timer),
+    Active {
+        definition: WaveDefinition,
+        spawned: u32,
+        killed: u32,
+        spawn_timer: Timer,
+    },
+}
+
+#[derive(Resource)]
+pub struct WaveDirector {
+    /// number of the current or last wave, 0 before the first one
+    pub wave: u32,
+    pub phase: WavePhase,
+    pub table: Handle<WaveTable>,
+}
+
+fn setup_wave_director(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(WaveDirector {
+        wave: 0,
+        phase: WavePhase::Loading,
+        table: asset_server.load(WAVE_TABLE_PATH),
+    });
+}
+
+/// Copies the loaded wave table into the `WaveTable` resource, unless it has errors.
+/// Also runs when the file is hot reloaded, an invalid edit keeps the previous table.
+fn apply_wave_table(
+    mut commands: Commands,
+    mut asset_events: EventReader<AssetEvent<WaveTable>>,
+    director: Res<WaveDirector>,
+    tables: Res<Assets<WaveTable>>,
+) {
+    for event in asset_events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event
+        else {
+            continue;
+        };
+
+        if *id != director.table.id() {
+            continue;
+        }
+
+        let Some(table) = tables.get(*id) else {
+            continue;
+        };
+
+        let errors = table.validate();
+
+        if !errors.is_empty() {
+            for error in errors {
+                error!("invalid {WAVE_TABLE_PATH}: {error}");
+            }
+
+            continue;
+        }
+
+        info!("loaded the waves from {WAVE_TABLE_PATH}");
+        commands.insert_resource(table.clone());
+    }
+}
+
+/// A new run starts over at the first wave.
+pub fn restart_waves(mut director: ResMut<WaveDirector>) {
+    director.wave = 0;
+    director.phase = WavePhase::Loading;
+}
+
+fn count_wave_kills(
+    mut death_events: EventReader<EntityDeathEvent>,
+    wave_mobs: Query<&WaveMob>,
+    mut director: ResMut<WaveDirector>,
+) {
+    let current_wave = director.wave;
+
+    let WavePhase::Active { killed, .. } = &mut director.phase else {
+        death_events.clear();
+        return;
+    };
+
+    for dead_entity in death_events.read().map(|event| event.entity).unique() {
+        if wave_mobs
+            .get(dead_entity)
+            .is_ok_and(|wave_mob| wave_mob.wave == current_wave)
+        {
+            *killed += 1;
+        }
+    }
+}
+
+fn run_wave_director(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut director: ResMut<WaveDirector>,
+    table: Option<Res<WaveTable>>,
+    grid: Res<TileGrid>,
+    biome_map: Res<BiomeMap>,
+    mob_kinds: MobKinds,
+    player_query: Query<&Transform, With<Player>>,
+    mut wave_started: EventWriter<WaveStarted>,
+    mut wave_cleared: EventWriter<WaveCleared>,
+    balance: Res<GameBalance>,
+) {
+    let WaveDirector { wave, phase, .. } = &mut *director;
+
+    let Some(table) = table else {
+        return;
+    };
+
+    match phase {
+        WavePhase::Loading => {
+            *phase = WavePhase::Calm(Timer::from_seconds(table.first_wave_delay, TimerMode::Once));
+        }
+        WavePhase::Calm(timer) => {
+            if !timer.tick(time.delta()).finished() {
+                return;
+            }
+
+            let Some(definition) = table.wave(*wave + 1) else {
+                return;
+            };
+
+            *wave += 1;
+
+            info!("wave {} started with {} mobs", wave, definition.mob_count);
+            wave_started.send(WaveStarted {
+                wave: *wave,
+                mob_count: definition.mob_count,
+            });
+
+            *phase = WavePhase::Active {
+                spawn_timer: Timer::from_seconds(definition.spawn_interval, TimerMode::Repeating),
+                definition,
+                spawned: 0,
+                killed: 0,
+            };
+        }
+        WavePhase::Active {
+            definition,
+            spawned,
+            killed,
+            spawn_timer,
+        } => {
+            if *killed >= definition.mob_count {
+                info!("wave {} cleared", wave);
+                wave_cleared.send(WaveCleared { wave: *wave });
+
+                *phase = WavePhase::Calm(Timer::from_seconds(table.calm_seconds, TimerMode::Once));
+                return;
+            }
+
+            if *spawned >= definition.mob_count || !spawn_timer.tick(time.delta()).just_finished() {
+                return;
+            }
+
+            let Ok(player_transform) = player_query.get_single() else {
+                return;
+            };
+
+            let mut rng = thread_rng();
+
+            let Some(spawn_point) = find_spawn_point(
+                &frontier_edges(&grid),
+                &biome_map,
+                player_transform.translation.xy(),
+                balance.spawner.min_distance,
+                &mut rng,
+            ) else {
+                return;
+            };
+
+            let spawn_table = if definition.mobs.is_empty() {
+                spawn_point.biome.definition().mobs
+            } else {
+                &definition.mobs
+            };
+
+            let Some(kind) = pick_mob_kind(spawn_table, &mut rng) else {
+                return;
+            };
+
+            let Some(stats) = mob_kinds.stats(kind) else {
+                return;
+            };
+
+            spawn_mob(&mut commands, kind, stats, spawn_point.position, &balance).insert((
+                WaveMob {
+                    wave: *wave,
+                    damage_multiplier: definition.damage_multiplier,
+                },
+                MobAi::new(stats, spawn_point.position).chasing(),
+                Health(stats.health * definition.health_multiplier),
+                Damage(
+                    stats.damage * definition.damage_multiplier,
+                    stats.damage_type,
+                ),
+            ));
+            *spawned += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> WaveTable {
+        let wave = |mob_count, health_multiplier| WaveDefinition {
+            mob_count,
+            spawn_interval: 1.,
+            health_multiplier,
+            damage_multiplier: 1.,
+            mobs: vec![],
+        };
+
+        WaveTable {
+            first_wave_delay: 5.,
+            calm_seconds: 10.,
+            waves: vec![wave(4, 1.), wave(8, 1.5)],
+            endless_scaling: WaveScaling {
+                mob_count: 1.5,
+                health: 2.,
+                damage: 1.25,
+            },
+        }
+    }
+
+    #[test]
+    fn defined_waves_are_returned_as_they_are() {
+        let table = table();
+
+        assert!(table.wave(0).is_none());
+        assert_eq!(table.wave(1).unwrap().mob_count, 4);
+        assert_eq!(table.wave(2).unwrap().health_multiplier, 1.5);
+    }
+
+    #[test]
+    fn waves_after_the_last_one_scale_it() {
+        let table = table();
+
+        let third = table.wave(3).unwrap();
+        assert_eq!(third.mob_count, 12);
+        assert_eq!(third.health_multiplier, 3.);
+        assert_eq!(third.damage_multiplier, 1.25);
+
+        let fourth = table.wave(4).unwrap();
+        assert_eq!(fourth.mob_count, 18);
+        assert_eq!(fourth.health_multiplier, 6.);
+        assert_eq!(fourth.damage_multiplier, 1.5625);
+    }
+
+    #[test]
+    fn an_empty_table_has_no_waves() {
+        let table = WaveTable {
+            waves: vec![],
+            ..table()
+        };
+
+        assert!(table.wave(1).is_none());
+    }
+
+    #[test]
+    fn shipped_table_is_valid() {
+        let table: WaveTable = ron::from_str(include_str!("../../assets/waves.ron")).unwrap();
+        let errors = table.validate();
+
+        assert!(errors.is_empty(), "{errors:?}");
+    }
+
+    #[test]
+    fn reports_bad_timers_and_multipliers() {
+        let mut table = table();
+        table.first_wave_delay = -1.;
+        table.waves[1].spawn_interval = 0.;
+        table.waves[1].damage_multiplier = -0.5;
+
+        let errors = table
+            .validate()
+            .into_iter()
+            .map(|error| error.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            errors,
+            [
+                "waves.1.spawn_interval has to be greater than 0, got 0",
+                "first_wave_delay can't be negative, got -1",
+                "waves.1.damage_multiplier can't be negative, got -0.5",
+            ]
+        );
+    }
+}
@@ -1,8 +1,11 @@
 #![allow(clippy::type_complexity)]
 #![allow(clippy::too_many_arguments)]
 
+use crate::balance::BalancePlugin;
 use crate::camera::CameraPlugin;
 use crate::entities::EntityPlugins;
+use crate::menu::MenuPlugin;
+use crate::save::SavePlugin;
 use crate::ui::UIPlugin;
 use crate::world::WorldPlugin;
 use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
@@ -10,8 +13,12 @@ use bevy::prelude::*;
 use bevy_xpbd_2d::plugins::*;
 use bevy_xpbd_2d::prelude::*;
 
+pub mod balance;
 pub mod camera;
 pub mod entities;
+pub mod menu;
+pub mod ron_asset;
+pub mod save;
 pub mod ui;
 pub mod world;
 
@@ -22,15 +29,30 @@ fn main() {
             PhysicsPlugins::default(),
         ))
         .add_plugins((FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin::default()))
+        .init_state::<AppState>()
+        .add_plugins(BalancePlugin)
         .add_plugins(EntityPlugins)
         .add_plugins(CameraPlugin)
         .add_plugins(WorldPlugin)
         .add_plugins(UIPlugin)
+        .add_plugins(SavePlugin)
+        .add_plugins(MenuPlugin)
         .insert_resource(Msaa::default())
         .insert_resource(Gravity::ZERO)
         .run();
 }
 
+/// A run starts when leaving the menu and ends when going back to it,
+/// pausing and dying keep the world around.
+#[derive(States, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AppState {
+    #[default]
+    Menu,
+    Playing,
+    Paused,
+    GameOver,
+}
+
 #[derive(PhysicsLayer)]
 pub enum PhysicsLayers {
     Player,
@@ -40,4 +62,5 @@ pub enum PhysicsLayers {
     Tree,
     ClosedTile,
     Fireball,
+    EnemyProjectile,
 }
@@ -0,0 +1,432 @@
+use crate::balance::GameBalance;
+use crate::entities::player::death::{RespawnPlayerEvent, RestartRunEvent, RunStats};
+use crate::AppState;
+use bevy::app::{App, AppExit, Plugin, Update};
+use bevy::prelude::*;
+use bevy::window::WindowMode;
+use bevy_xpbd_2d::prelude::{Physics, PhysicsTime};
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunStart>()
+            .add_systems(OnEnter(AppState::Menu), setup_main_menu)
+            .add_systems(OnExit(AppState::Menu), despawn_screen::<MainMenu>)
+            .add_systems(OnEnter(AppState::GameOver), setup_game_over_screen)
+            .add_systems(OnExit(AppState::GameOver), despawn_screen::<GameOverScreen>)
+            .add_systems(OnEnter(AppState::Paused), (pause_time, setup_pause_menu))
+            .add_systems(
+                OnExit(AppState::Paused),
+                (
+                    unpause_time,
+                    despawn_screen::<PauseMenu>,
+                    despawn_screen::<SettingsMenu>,
+                ),
+            )
+            .add_systems(Update, highlight_buttons)
+            .add_systems(
+                Update,
+                toggle_pause
+                    .run_if(in_state(AppState::Playing).or_else(in_state(AppState::Paused))),
+            )
+            .add_systems(
+                Update,
+                (press_pause_buttons, press_settings_buttons).run_if(in_state(AppState::Paused)),
+            )
+            .add_systems(
+                Update,
+                (press_main_menu_buttons, skip_menu_on_restart).run_if(in_state(AppState::Menu)),
+            )
+            .add_systems(
+                Update,
+                press_game_over_buttons.run_if(in_state(AppState::GameOver)),
+            );
+    }
+}
+
+pub static BUTTON_COLOR: Color = Color::rgb(0.2, 0.2, 0.25);
+pub static BUTTON_HOVER_COLOR: Color = Color::rgb(0.3, 0.3, 0.4);
+pub static PAUSE_KEY: KeyCode = KeyCode::Escape;
+
+/// How the next run starts, set by the button that leaves the menu.
+#[derive(Resource)]
+pub struct RunStart {
+    /// continue from the save file instead of a new world
+    pub load_save: bool,
+}
+
+impl Default for RunStart {
+    fn default() -> RunStart {
+        RunStart { load_save: true }
+    }
+}
+
+#[derive(Component)]
+pub struct MainMenu;
+
+#[derive(Component, Clone, Copy)]
+pub enum MainMenuButton {
+    /// continues from the save file, or starts a new world if there is none
+    Continue,
+    NewGame,
+    Quit,
+}
+
+#[derive(Component)]
+pub struct GameOverScreen;
+
+#[derive(Component, Clone, Copy)]
+pub enum GameOverButton {
+    /// back to the last checkpoint in the same world
+    Respawn,
+    /// new world from scratch
+    Restart,
+    MainMenu,
+}
+
+#[derive(Component)]
+pub struct PauseMenu;
+
+#[derive(Component, Clone, Copy)]
+pub enum PauseButton {
+    Resume,
+    Settings,
+    /// back to the main menu, the run is lost unless it was saved
+    Quit,
+}
+
+#[derive(Component)]
+pub struct SettingsMenu;
+
+#[derive(Component, Clone, Copy)]
+pub enum SettingsButton {
+    Fullscreen,
+    /// back to the pause menu
+    Back,
+}
+
+pub fn despawn_screen<T: Component>(mut commands: Commands, screens: Query<Entity, With<T>>) {
+    for screen in screens.iter() {
+        commands.entity(screen).despawn_recursive();
+    }
+}
+
+/// Full screen column the menus are laid out in.
+pub fn screen<T: Component>(marker: T, background: Color) -> (T, NodeBundle) {
+    (
+        marker,
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(12.),
+                ..default()
+            },
+            background_color: background.into(),
+            z_index: ZIndex::Global(10),
+            ..default()
+        },
+    )
+}
+
+pub fn title(text: &str, color: Color) -> TextBundle {
+    TextBundle::from_section(
+        text,
+        TextStyle {
+            font_size: 48.,
+            color,
+            ..default()
+        },
+    )
+}
+
+pub fn menu_button<T: Component>(marker: T) -> (T, ButtonBundle) {
+    (
+        marker,
+        ButtonBundle {
+            style: Style {
+                width: Val::Px(180.),
+                height: Val::Px(40.),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: BUTTON_COLOR.into(),
+            ..default()
+        },
+    )
+}
+
+pub fn menu_button_label(label: &str) -> TextBundle {
+    TextBundle::from_section(
+        label,
+        TextStyle {
+            font_size: 22.,
+            color: Color::WHITE,
+            ..default()
+        },
+    )
+}
+
+fn setup_main_menu(mut commands: Commands) {
+    commands
+        .spawn(screen(MainMenu, Color::rgb(0.05, 0.08, 0.12)))
+        .with_children(|parent| {
+            parent.spawn(title("Frozen Forest", Color::rgb(0.7, 0.85, 1.)));
+
+            for (button, label) in [
+                (MainMenuButton::Continue, "Continue"),
+                (MainMenuButton::NewGame, "New game"),
+                (MainMenuButton::Quit, "Quit"),
+            ] {
+                parent.spawn(menu_button(button)).with_children(|parent| {
+                    parent.spawn(menu_button_label(label));
+                });
+            }
+        });
+}
+
+fn setup_game_over_screen(mut commands: Commands, stats: Res<RunStats>) {
+    let stats = TextBundle::from_section(
+        format!(
+            "Survived {:.0}s\nMobs killed: {}\nBosses defeated: {}\nDeaths: {}",
+            stats.seconds, stats.mobs_killed, stats.bosses_defeated, stats.deaths
+        ),
+        TextStyle {
+            font_size: 20.,
+            color: Color::WHITE,
+            ..default()
+        },
+    );
+
+    commands
+        .spawn(screen(GameOverScreen, Color::rgba(0., 0., 0., 0.7)))
+        .with_children(|parent| {
+            parent.spawn(title("You died", Color::ORANGE_RED));
+            parent.spawn(stats);
+
+            for (button, label) in [
+                (GameOverButton::Respawn, "Respawn"),
+                (GameOverButton::Restart, "New run"),
+                (GameOverButton::MainMenu, "Main menu"),
+            ] {
+                parent.spawn(menu_button(button)).with_children(|parent| {
+                    parent.spawn(menu_button_label(label));
+                });
+            }
+        });
+}
+
+fn highlight_buttons(
+    mut buttons: Query<(&Interaction, &mut BackgroundColor), (Changed<Interaction>, With<Button>)>,
+) {
+    for (interaction, mut color) in buttons.iter_mut() {
+        *color = match interaction {
+            Interaction::Hovered | Interaction::Pressed => BUTTON_HOVER_COLOR.into(),
+            Interaction::None => BUTTON_COLOR.into(),
+        };
+    }
+}
+
+fn press_main_menu_buttons(
+    buttons: Query<(&Interaction, &MainMenuButton), Changed<Interaction>>,
+    mut run_start: ResMut<RunStart>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut exit: EventWriter<AppExit>,
+    balance: Option<Res<GameBalance>>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        match button {
+            MainMenuButton::Continue | MainMenuButton::NewGame => {
+                // a run needs the balance, it is missing while loading or if the file is invalid
+                if balance.is_none() {
+                    warn!("the game balance isn't loaded yet");
+                    continue;
+                }
+
+                run_start.load_save = matches!(button, MainMenuButton::Continue);
+                next_state.set(AppState::Playing);
+            }
+            MainMenuButton::Quit => {
+                exit.send(AppExit);
+            }
+        }
+    }
+}
+
+/// "New run" goes through the menu so the old run is torn down, but doesn't stop there.
+fn skip_menu_on_restart(
+    mut restart_events: EventReader<RestartRunEvent>,
+    mut run_start: ResMut<RunStart>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if restart_events.read().count() == 0 {
+        return;
+    }
+
+    run_start.load_save = false;
+    next_state.set(AppState::Playing);
+}
+
+fn press_game_over_buttons(
+    buttons: Query<(&Interaction, &GameOverButton), Changed<Interaction>>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut respawn_events: EventWriter<RespawnPlayerEvent>,
+    mut restart_events: EventWriter<RestartRunEvent>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        match button {
+            GameOverButton::Respawn => {
+                respawn_events.send(RespawnPlayerEvent);
+                next_state.set(AppState::Playing);
+            }
+            GameOverButton::Restart => {
+                restart_events.send(RestartRunEvent);
+                next_state.set(AppState::Menu);
+            }
+            GameOverButton::MainMenu => {
+                next_state.set(AppState::Menu);
+            }
+        }
+    }
+}
+
+/// Escape pauses the game and resumes it again from the pause or settings menu.
+fn toggle_pause(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    state: Res<State<AppState>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if !keyboard_input.just_pressed(PAUSE_KEY) {
+        return;
+    }
+
+    match state.get() {
+        AppState::Playing => next_state.set(AppState::Paused),
+        AppState::Paused => next_state.set(AppState::Playing),
+        _ => {}
+    }
+}
+
+/// Stops the physics and every timer ticked with `Time`, gameplay systems don't run anyway.
+fn pause_time(mut time: ResMut<Time<Virtual>>, mut physics_time: ResMut<Time<Physics>>) {
+    time.pause();
+    physics_time.pause();
+}
+
+fn unpause_time(mut time: ResMut<Time<Virtual>>, mut physics_time: ResMut<Time<Physics>>) {
+    time.unpause();
+    physics_time.unpause();
+}
+
+fn setup_pause_menu(mut commands: Commands) {
+    spawn_pause_menu(&mut commands);
+}
+
+fn spawn_pause_menu(commands: &mut Commands) {
+    commands
+        .spawn(screen(PauseMenu, Color::rgba(0., 0., 0., 0.5)))
+        .with_children(|parent| {
+            parent.spawn(title("Paused", Color::WHITE));
+
+            for (button, label) in [
+                (PauseButton::Resume, "Resume"),
+                (PauseButton::Settings, "Settings"),
+                (PauseButton::Quit, "Quit"),
+            ] {
+                parent.spawn(menu_button(button)).with_children(|parent| {
+                    parent.spawn(menu_button_label(label));
+                });
+            }
+        });
+}
+
+fn spawn_settings_menu(commands: &mut Commands) {
+    commands
+        .spawn(screen(SettingsMenu, Color::rgba(0., 0., 0., 0.5)))
+        .with_children(|parent| {
+            parent.spawn(title("Settings", Color::WHITE));
+
+            for (button, label) in [
+                (SettingsButton::Fullscreen, "Fullscreen"),
+                (SettingsButton::Back, "Back"),
+            ] {
+                parent.spawn(menu_button(button)).with_children(|parent| {
+                    parent.spawn(menu_button_label(label));
+                });
+            }
+        });
+}
+
+fn press_pause_buttons(
+    mut commands: Commands,
+    buttons: Query<(&Interaction, &PauseButton), Changed<Interaction>>,
+    pause_menu: Query<Entity, With<PauseMenu>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        match button {
+            PauseButton::Resume => {
+                next_state.set(AppState::Playing);
+            }
+            PauseButton::Settings => {
+                for menu in pause_menu.iter() {
+                    commands.entity(menu).despawn_recursive();
+                }
+
+                spawn_settings_menu(&mut commands);
+            }
+            PauseButton::Quit => {
+                next_state.set(AppState::Menu);
+            }
+        }
+    }
+}
+
+fn press_settings_buttons(
+    mut commands: Commands,
+    buttons: Query<(&Interaction, &SettingsButton), Changed<Interaction>>,
+    settings_menu: Query<Entity, With<SettingsMenu>>,
+    mut windows: Query<&mut Window>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        match button {
+            SettingsButton::Fullscreen => {
+                for mut window in windows.iter_mut() {
+                    window.mode = match window.mode {
+                        WindowMode::Windowed => WindowMode::BorderlessFullscreen,
+                        _ => WindowMode::Windowed,
+                    };
+                }
+            }
+            SettingsButton::Back => {
+                for menu in settings_menu.iter() {
+                    commands.entity(menu).despawn_recursive();
+                }
+
+                spawn_pause_menu(&mut commands);
+            }
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use bevy::asset::io::Reader;
+use bevy::asset::{Asset, AssetLoader, AsyncReadExt, LoadContext};
+use bevy::utils::BoxedFuture;
+use iter_tools::Itertools;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::marker::PhantomData;
+
+/// Loads any deserializable asset from a RON file, e.g. `waves.ron`.
+pub struct RonAssetLoader<A> {
+    extensions: &'static [&'static str],
+    phantom: PhantomData<fn() -> A>,
+}
+
+impl<A> RonAssetLoader<A> {
+    pub fn new(extensions: &'static [&'static str]) -> Self {
+        RonAssetLoader {
+            extensions,
+            phantom: PhantomData,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum RonAssetError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+}
+
+impl Display for RonAssetError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RonAssetError::Io(error) => write!(f, "could not read file: {error}"),
+            RonAssetError::Ron(error) => write!(f, "invalid RON: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for RonAssetError {}
+
+impl<A> AssetLoader for RonAssetLoader<A>
+where
+    A: Asset + for<'de> Deserialize<'de>,
+{
+    type Asset = A;
+    type Settings = ();
+    type Error = RonAssetError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<A, RonAssetError>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader
+                .read_to_end(&mut bytes)
+                .await
+                .map_err(RonAssetError::Io)?;
+
+            ron::de::from_bytes(&bytes).map_err(RonAssetError::Ron)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        self.extensions
+    }
+}
+
+/// Every value that differs between two versions of an asset, e.g. `Wolf.speed: 140.0 -> 160.0`.
+/// Used to log what changed when a RON file is hot reloaded.
+pub fn ron_diff<T: Serialize>(old: &T, new: &T) -> Vec<String> {
+    let (Ok(old), Ok(new)) = (
+        ron::ser::to_string_pretty(old, PrettyConfig::default()),
+        ron::ser::to_string_pretty(new, PrettyConfig::default()),
+    ) else {
+        return vec![];
+    };
+
+    let old = ron_values(&old);
+    let new = ron_values(&new);
+
+    let mut changes = vec![];
+
+    for (path, old_value) in old.iter() {
+        match new.get(path) {
+            Some(new_value) if new_value == old_value => {}
+            Some(new_value) => changes.push(format!("{path}: {old_value} -> {new_value}")),
+            None => changes.push(format!("{path}: {old_value} -> removed")),
+        }
+    }
+
+    for (path, new_value) in new.iter() {
+        if !old.contains_key(path) {
+            changes.push(format!("{path}: added {new_value}"));
+        }
+    }
+
+    changes
+}
+
+/// Flattens pretty printed RON into the value of every line by its path, e.g. `player.speed`.
+/// Map entries use their key in the path, so the order of hash maps doesn't matter.
+fn ron_values(pretty: &str) -> BTreeMap<String, String> {
+    let mut values = BTreeMap::new();
+    // open brackets with their name and the number of unnamed items seen in them
+    let mut path: Vec<(String, usize)> = vec![];
+
+    for line in pretty.lines() {
+        let line = line.trim().trim_end_matches(',');
+
+        if line.is_empty() {
+            continue;
+        }
+
+        if line.starts_with([')', ']', '}']) {
+            path.pop();
+            continue;
+        }
+
+        let (name, value) = match line.split_once(": ") {
+            Some((name, value)) => (name.to_string(), value),
+            // tuple and list items are named by their index
+            None => match path.last_mut() {
+                Some((_, index)) => {
+                    *index += 1;
+                    ((*index - 1).to_string(), line)
+                }
+                None => (String::new(), line),
+            },
+        };
+
+        let full_path = path
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .chain([name.as_str()])
+            .filter(|name| !name.is_empty())
+            .join(".");
+
+        // openers like `Some((` or `Ranged(` are kept so a changed variant shows up
+        values.insert(full_path, value.to_string());
+
+        if line.ends_with(['(', '[', '{']) {
+            path.push((name, 0));
+        }
+    }
+
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[derive(Serialize)]
+    struct Stats {
+        health: u32,
+        drops: Vec<u32>,
+        kinds: HashMap<String, u32>,
+    }
+
+    fn stats() -> Stats {
+        Stats {
+            health: 10,
+            drops: vec![1, 2, 3],
+            kinds: HashMap::from([
+                ("wolf".to_string(), 1),
+                ("golem".to_string(), 2),
+                ("wisp".to_string(), 3),
+                ("yeti".to_string(), 4),
+            ]),
+        }
+    }
+
+    #[test]
+    fn equal_values_have_no_diff() {
+        assert!(ron_diff(&stats(), &stats()).is_empty());
+    }
+
+    #[test]
+    fn reports_changed_fields_and_list_items_by_path() {
+        let mut new = stats();
+        new.health = 12;
+        new.drops[1] = 5;
+
+        assert_eq!(
+            ron_diff(&stats(), &new),
+            vec!["drops.1: 2 -> 5", "health: 10 -> 12"]
+        );
+    }
+
+    #[test]
+    fn reports_added_and_removed_map_entries() {
+        let mut new = stats();
+        new.kinds.remove("wisp");
+        new.kinds.insert("bear".to_string(), 5);
+
+        assert_eq!(
+            ron_diff(&stats(), &new),
+            vec!["kinds.\"wisp\": 3 -> removed", "kinds.\"bear\": added 5"]
+        );
+    }
+}
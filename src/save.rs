@@ -0,0 +1,588 @@
+use crate::balance::GameBalance;
+use crate::entities::boss::Boss;
+use crate::entities::data::{Health, Mob, Player};
+use crate::entities::mob::spawn_mob;
+use crate::entities::mob_kind::{MobKind, MobKinds};
+use crate::entities::player::death::Checkpoint;
+use crate::entities::player::player_setup;
+use crate::entities::waves::{restart_waves, WaveDirector, WavePhase};
+use crate::menu::RunStart;
+use crate::ui::{AttackType, Inventory, InventorySlot, SelectedSlot};
+use crate::world::biome::BiomeMap;
+use crate::world::grid::{TileChanges, TileGrid, TileState};
+use crate::world::seed::WorldSeed;
+use crate::world::unlock::Currency;
+use crate::world::{closed_tile, open_tile, start_world, Tile};
+use crate::AppState;
+use bevy::prelude::*;
+use bevy_xpbd_2d::prelude::Position;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PendingMobs>()
+            .add_systems(
+                OnExit(AppState::Menu),
+                load_save
+                    .after(start_world)
+                    .after(player_setup)
+                    .after(crate::ui::setup)
+                    .after(restart_waves)
+                    .run_if(|run_start: Res<RunStart>| run_start.load_save),
+            )
+            .add_systems(OnEnter(AppState::Menu), clear_pending_mobs)
+            .add_systems(
+                Update,
+                (save_on_key, spawn_saved_mobs).run_if(in_state(AppState::Playing)),
+            );
+    }
+}
+
+/// Bump this whenever the save format changes and upgrade the old version in `parse_save`.
+pub static SAVE_VERSION: u32 = 5;
+pub static SAVE_PATH: &str = "saves/save.ron";
+pub static SAVE_KEY: KeyCode = KeyCode::F5;
+
+#[derive(Serialize, Deserialize)]
+pub struct SaveData {
+    pub version: u32,
+    pub seed: u64,
+    pub currency: u32,
+    pub tiles: Vec<SavedTile>,
+    pub player: SavedPlayer,
+    pub selected_slot: usize,
+    pub inventory: Vec<SavedSlot>,
+    /// added in version 2
+    pub wood: u32,
+    /// bosses and wave membership are not saved, bosses respawn in their lair and
+    /// mobs of an unfinished wave load as ordinary mobs
+    pub mobs: Vec<SavedMob>,
+    /// last cleared wave, the next one starts after the first wave delay,
+    /// added in version 5
+    pub wave: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedTile {
+    pub position: IVec2,
+    pub state: TileState,
+    pub changes: TileChanges,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedPlayer {
+    pub position: Vec2,
+    pub health: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedSlot {
+    pub index: usize,
+    pub attack: Option<AttackType>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedMob {
+    /// added in version 3
+    pub kind: MobKind,
+    pub position: Vec2,
+    pub health: f64,
+}
+
+/// Only the version, read before the rest of the save to know which format to parse it as.
+#[derive(Deserialize)]
+struct SaveHeader {
+    version: u32,
+}
+
+/// Version 1, before wood and mob kinds.
+#[derive(Deserialize)]
+struct SaveV1 {
+    seed: u64,
+    currency: u32,
+    tiles: Vec<SavedTile>,
+    player: SavedPlayer,
+    selected_slot: usize,
+    inventory: Vec<SavedSlot>,
+    mobs: Vec<SavedMobV1>,
+}
+
+#[derive(Deserialize)]
+struct SavedMobV1 {
+    position: Vec2,
+    health: f64,
+}
+
+/// Version 2 added wood.
+#[derive(Deserialize)]
+struct SaveV2 {
+    seed: u64,
+    currency: u32,
+    tiles: Vec<SavedTile>,
+    player: SavedPlayer,
+    selected_slot: usize,
+    inventory: Vec<SavedSlot>,
+    wood: u32,
+    mobs: Vec<SavedMobV1>,
+}
+
+/// Version 3 added mob kinds, version 4 kept the format and only stopped saving bosses.
+#[derive(Deserialize)]
+struct SaveV3 {
+    seed: u64,
+    currency: u32,
+    tiles: Vec<SavedTile>,
+    player: SavedPlayer,
+    selected_slot: usize,
+    inventory: Vec<SavedSlot>,
+    wood: u32,
+    mobs: Vec<SavedMob>,
+}
+
+impl SaveV1 {
+    /// Trees felled before weren't collected, so there is no wood yet.
+    fn upgrade(self) -> SaveV2 {
+        SaveV2 {
+            seed: self.seed,
+            currency: self.currency,
+            tiles: self.tiles,
+            player: self.player,
+            selected_slot: self.selected_slot,
+            inventory: self.inventory,
+            wood: 0,
+            mobs: self.mobs,
+        }
+    }
+}
+
+impl SaveV2 {
+    /// Every mob before version 3 was a wolf.
+    fn upgrade(self) -> SaveV3 {
+        SaveV3 {
+            seed: self.seed,
+            currency: self.currency,
+            tiles: self.tiles,
+            player: self.player,
+            selected_slot: self.selected_slot,
+            inventory: self.inventory,
+            wood: self.wood,
+            mobs: self
+                .mobs
+                .into_iter()
+                .map(|mob| SavedMob {
+                    kind: MobKind::Wolf,
+                    position: mob.position,
+                    health: mob.health,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl SaveV3 {
+    /// Bosses respawn in their lair since version 4, saved ones would be there twice.
+    fn without_bosses(mut self) -> SaveV3 {
+        self.mobs.retain(|mob| mob.kind != MobKind::Yeti);
+        self
+    }
+
+    /// Saves before version 5 start over at the first wave.
+    fn upgrade(self) -> SaveData {
+        SaveData {
+            version: SAVE_VERSION,
+            seed: self.seed,
+            currency: self.currency,
+            tiles: self.tiles,
+            player: self.player,
+            selected_slot: self.selected_slot,
+            inventory: self.inventory,
+            wood: self.wood,
+            mobs: self.mobs,
+            wave: 0,
+        }
+    }
+}
+
+/// Mobs from the save that wait for the mob registry to finish loading.
+#[derive(Resource, Default)]
+pub struct PendingMobs(pub Vec<SavedMob>);
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(std::io::Error),
+    Serialize(ron::Error),
+    Deserialize(ron::error::SpannedError),
+    /// the save was written by a newer version of the game
+    UnsupportedVersion(u32),
+}
+
+/// Reads the save file, returns `Ok(None)` if there is none yet.
+pub fn read_save(path: &Path) -> Result<Option<SaveData>, SaveError> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let contents = fs::read_to_string(path).map_err(SaveError::Io)?;
+
+    parse_save(&contents).map(Some)
+}
+
+/// Parses a save in the format of the version it was written with
+/// and upgrades it step by step to the current one.
+fn parse_save(contents: &str) -> Result<SaveData, SaveError> {
+    fn parse<'a, T: Deserialize<'a>>(contents: &'a str) -> Result<T, SaveError> {
+        ron::from_str(contents).map_err(SaveError::Deserialize)
+    }
+
+    let header: SaveHeader = parse(contents)?;
+
+    if header.version > SAVE_VERSION {
+        return Err(SaveError::UnsupportedVersion(header.version));
+    }
+
+    if header.version < SAVE_VERSION {
+        info!(
+            "migrating save from version {} to {}",
+            header.version, SAVE_VERSION
+        );
+    }
+
+    let save = match header.version {
+        0 | 1 => parse::<SaveV1>(contents)?
+            .upgrade()
+            .upgrade()
+            .without_bosses()
+            .upgrade(),
+        2 => parse::<SaveV2>(contents)?
+            .upgrade()
+            .without_bosses()
+            .upgrade(),
+        3 => parse::<SaveV3>(contents)?.without_bosses().upgrade(),
+        4 => parse::<SaveV3>(contents)?.upgrade(),
+        _ => parse::<SaveData>(contents)?,
+    };
+
+    Ok(save)
+}
+
+/// Writes to a temporary file next to the save first, so a crash while writing
+/// never leaves a half written save behind.
+pub fn write_save(path: &Path, save: &SaveData) -> Result<(), SaveError> {
+    let contents =
+        ron::ser::to_string_pretty(save, PrettyConfig::default()).map_err(SaveError::Serialize)?;
+
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory).map_err(SaveError::Io)?;
+    }
+
+    let temporary_path = path.with_extension("ron.tmp");
+
+    fs::write(&temporary_path, contents).map_err(SaveError::Io)?;
+    fs::rename(&temporary_path, path).map_err(SaveError::Io)
+}
+
+fn save_on_key(
+    keys: Res<ButtonInput<KeyCode>>,
+    seed: Res<WorldSeed>,
+    currency: Res<Currency>,
+    grid: Res<TileGrid>,
+    player_query: Query<(&Transform, &Health), With<Player>>,
+    // bosses are not saved, they respawn in their lair
+    mob_query: Query<(&MobKind, &Transform, &Health), (With<Mob>, Without<Boss>)>,
+    inventory: Query<&InventorySlot>,
+    selected_slot: Res<SelectedSlot>,
+    materials: Res<Inventory>,
+    director: Res<WaveDirector>,
+    mut checkpoint: ResMut<Checkpoint>,
+) {
+    if !keys.just_pressed(SAVE_KEY) {
+        return;
+    }
+
+    let Ok((player_transform, player_health)) = player_query.get_single() else {
+        warn!("could not save, no single Player found");
+        return;
+    };
+
+    let save = SaveData {
+        version: SAVE_VERSION,
+        seed: seed.0,
+        currency: currency.0,
+        tiles: grid
+            .iter()
+            .map(|(position, tile)| SavedTile {
+                position,
+                state: tile.state,
+                changes: tile.changes.clone(),
+            })
+            .collect(),
+        player: SavedPlayer {
+            position: player_transform.translation.xy(),
+            health: player_health.0,
+        },
+        selected_slot: selected_slot.index,
+        inventory: inventory
+            .iter()
+            .map(|slot| SavedSlot {
+                index: slot.index,
+                attack: slot.attack,
+            })
+            .collect(),
+        wood: materials.wood,
+        mobs: mob_query
+            .iter()
+            .map(|(kind, transform, health)| SavedMob {
+                kind: *kind,
+                position: transform.translation.xy(),
+                health: health.0,
+            })
+            .collect(),
+        wave: match director.phase {
+            WavePhase::Active { .. } => director.wave - 1,
+            _ => director.wave,
+        },
+    };
+
+    match write_save(Path::new(SAVE_PATH), &save) {
+        Ok(()) => {
+            // the player respawns where they last saved
+            checkpoint.0 = save.player.position;
+            info!("saved the game to {SAVE_PATH}");
+        }
+        Err(error) => error!("could not save the game: {error:?}"),
+    }
+}
+
+/// Replaces the freshly generated world with the one from the save file, if there is one.
+fn load_save(
+    mut commands: Commands,
+    mut grid: ResMut<TileGrid>,
+    mut currency: ResMut<Currency>,
+    mut selected_slot: ResMut<SelectedSlot>,
+    mut materials: ResMut<Inventory>,
+    mut pending_mobs: ResMut<PendingMobs>,
+    mut checkpoint: ResMut<Checkpoint>,
+    mut director: ResMut<WaveDirector>,
+    tiles: Query<Entity, With<Tile>>,
+    mobs: Query<Entity, With<Mob>>,
+    mut player_query: Query<(Entity, &mut Transform, &mut Health), With<Player>>,
+    mut inventory: Query<&mut InventorySlot>,
+) {
+    let save = match read_save(Path::new(SAVE_PATH)) {
+        Ok(Some(save)) => save,
+        Ok(None) => return,
+        Err(error) => {
+            error!("could not load the save, starting a new game: {error:?}");
+            return;
+        }
+    };
+
+    let seed = WorldSeed(save.seed);
+    let biome_map = BiomeMap::new(&seed);
+
+    for tile in tiles.iter() {
+        commands.entity(tile).despawn_recursive();
+    }
+
+    *grid = TileGrid::default();
+
+    for saved_tile in save.tiles {
+        match saved_tile.state {
+            TileState::Open => open_tile(&mut commands, &mut grid, &biome_map, saved_tile.position),
+            TileState::Closed => closed_tile(&mut commands, &mut grid, saved_tile.position),
+        }
+
+        if let Some(tile) = grid.get_mut(saved_tile.position) {
+            tile.changes = saved_tile.changes;
+        }
+    }
+
+    commands.insert_resource(seed);
+    commands.insert_resource(biome_map);
+
+    currency.0 = save.currency;
+    checkpoint.0 = save.player.position;
+
+    if let Ok((player, mut transform, mut health)) = player_query.get_single_mut() {
+        transform.translation = save.player.position.extend(transform.translation.z);
+        health.0 = save.player.health;
+        commands
+            .entity(player)
+            .insert(Position(save.player.position));
+    }
+
+    selected_slot.index = save.selected_slot;
+    materials.wood = save.wood;
+
+    for mut slot in inventory.iter_mut() {
+        slot.attack = save
+            .inventory
+            .iter()
+            .find(|saved_slot| saved_slot.index == slot.index)
+            .and_then(|saved_slot| saved_slot.attack);
+    }
+
+    for mob in mobs.iter() {
+        commands.entity(mob).despawn_recursive();
+    }
+
+    pending_mobs.0 = save.mobs;
+
+    // `restart_waves` already went back to loading, only the wave number carries over
+    director.wave = save.wave;
+
+    info!("loaded the save from {SAVE_PATH}, world seed: {}", seed.0);
+}
+
+fn clear_pending_mobs(mut pending_mobs: ResMut<PendingMobs>) {
+    pending_mobs.0.clear();
+}
+
+fn spawn_saved_mobs(
+    mut commands: Commands,
+    mut pending_mobs: ResMut<PendingMobs>,
+    mob_kinds: MobKinds,
+    balance: Res<GameBalance>,
+) {
+    if pending_mobs.0.is_empty() || !mob_kinds.is_loaded() {
+        return;
+    }
+
+    for saved_mob in pending_mobs.0.drain(..) {
+        let Some(stats) = mob_kinds.stats(saved_mob.kind) else {
+            continue;
+        };
+
+        spawn_mob(
+            &mut commands,
+            saved_mob.kind,
+            stats,
+            saved_mob.position,
+            &balance,
+        )
+        .insert(Health(saved_mob.health));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn save() -> SaveData {
+        SaveData {
+            version: SAVE_VERSION,
+            seed: 42,
+            currency: 120,
+            tiles: vec![SavedTile {
+                position: IVec2::new(1, -1),
+                state: TileState::Open,
+                changes: TileChanges {
+                    felled_trees: vec![UVec2::new(3, 4)],
+                    boss_defeated: true,
+                },
+            }],
+            player: SavedPlayer {
+                position: Vec2::new(10., -20.),
+                health: 75.,
+            },
+            selected_slot: 1,
+            inventory: vec![SavedSlot {
+                index: 1,
+                attack: Some(AttackType::Fireball),
+            }],
+            wood: 7,
+            mobs: vec![SavedMob {
+                kind: MobKind::Wisp,
+                position: Vec2::new(-5., 5.),
+                health: 12.5,
+            }],
+            wave: 3,
+        }
+    }
+
+    #[test]
+    fn written_saves_read_back_the_same() {
+        let directory = std::env::temp_dir().join(format!("save_test_{}", std::process::id()));
+        let path = directory.join("save.ron");
+        let save = save();
+
+        write_save(&path, &save).unwrap();
+        let read = read_save(&path).unwrap().unwrap();
+
+        assert_eq!(
+            ron::to_string(&read).unwrap(),
+            ron::to_string(&save).unwrap()
+        );
+        assert!(!path.with_extension("ron.tmp").exists());
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn missing_saves_are_not_an_error() {
+        let path = std::env::temp_dir().join("no_save_here.ron");
+
+        assert!(read_save(&path).unwrap().is_none());
+    }
+
+    #[test]
+    fn saves_from_newer_versions_are_rejected() {
+        let contents = format!("(version: {})", SAVE_VERSION + 1);
+
+        assert!(matches!(
+            parse_save(&contents),
+            Err(SaveError::UnsupportedVersion(version)) if version == SAVE_VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn version_3_saves_drop_their_bosses() {
+        let contents = r#"(
+            version: 3,
+            seed: 42,
+            currency: 120,
+            tiles: [],
+            player: (position: (10.0, -20.0), health: 75.0),
+            selected_slot: 0,
+            inventory: [],
+            wood: 7,
+            mobs: [
+                (kind: IceGolem, position: (0.0, 0.0), health: 30.0),
+                (kind: Yeti, position: (100.0, 0.0), health: 500.0),
+            ],
+        )"#;
+
+        let save = parse_save(contents).unwrap();
+
+        assert_eq!(save.version, SAVE_VERSION);
+        assert_eq!(save.wood, 7);
+        assert_eq!(save.wave, 0);
+        assert_eq!(save.mobs.len(), 1);
+        assert_eq!(save.mobs[0].kind, MobKind::IceGolem);
+    }
+
+    #[test]
+    fn version_1_saves_get_wolves_and_no_wood() {
+        let contents = r#"(
+            version: 1,
+            seed: 42,
+            currency: 120,
+            tiles: [],
+            player: (position: (10.0, -20.0), health: 75.0),
+            selected_slot: 0,
+            inventory: [],
+            mobs: [(position: (0.0, 0.0), health: 30.0)],
+        )"#;
+
+        let save = parse_save(contents).unwrap();
+
+        assert_eq!(save.wood, 0);
+        assert_eq!(save.mobs.len(), 1);
+        assert_eq!(save.mobs[0].kind, MobKind::Wolf);
+    }
+}
@@ -1,23 +1,62 @@
-use crate::entities::data::{Health, Player, MAX_PLAYER_HEALTH};
-use bevy::app::{App, Startup};
+use crate::balance::GameBalance;
+use crate::entities::ai::{MobAi, MobState};
+use crate::entities::boss::Boss;
+use crate::entities::data::{Health, Player};
+use crate::entities::mob_kind::MobKind;
+use crate::entities::waves::{WaveDirector, WavePhase};
+use crate::world::unlock::{ActivationRejection, Currency, TileActivationRejectedEvent};
+use crate::AppState;
+use bevy::app::App;
 use bevy::input::mouse::MouseWheel;
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
 pub struct UIPlugin;
 
 impl Plugin for UIPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, setup_health_bar)
-            .add_systems(Update, update_health_bar)
-            .add_systems(Startup, setup)
-            .add_systems(Update, set_border_color)
+        app.init_resource::<Inventory>()
+            .add_systems(
+                OnExit(AppState::Menu),
+                (
+                    setup_health_bar,
+                    setup_boss_health_bar,
+                    setup,
+                    setup_currency_display,
+                ),
+            )
+            .add_systems(OnEnter(AppState::Menu), despawn_hud)
             .add_systems(
                 Update,
-                (select_inventory_slot_keyboard, select_inventory_slot_wheel),
+                (
+                    update_health_bar,
+                    update_boss_health_bar,
+                    set_border_color,
+                    select_inventory_slot_keyboard,
+                    select_inventory_slot_wheel,
+                    update_currency_display,
+                    update_wood_display,
+                    update_wave_display,
+                    (show_rejection_message, hide_rejection_message).chain(),
+                )
+                    .run_if(in_state(AppState::Playing)),
             );
     }
 }
 
+pub static REJECTION_MESSAGE_SECONDS: f32 = 2.;
+pub static INVENTORY_SLOTS: usize = 5;
+
+/// Root of every ui node that belongs to a run, removed when going back to the menu.
+#[derive(Component)]
+pub struct Hud;
+
+pub fn despawn_hud(mut commands: Commands, hud: Query<Entity, With<Hud>>) {
+    for entity in hud.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
 #[derive(Component)]
 pub struct HealthBar;
 
@@ -46,7 +85,7 @@ pub fn setup_health_bar(mut commands: Commands) {
         },
     );
 
-    commands.spawn(container).with_children(|parent| {
+    commands.spawn((Hud, container)).with_children(|parent| {
         parent.spawn(bar);
     });
 }
@@ -54,9 +93,10 @@ pub fn setup_health_bar(mut commands: Commands) {
 pub fn update_health_bar(
     player_query: Query<&Health, With<Player>>,
     mut health_bar_query: Query<&mut Style, With<HealthBar>>,
+    balance: Res<GameBalance>,
 ) {
+    // no player outside of a run
     let Ok(health) = player_query.get_single() else {
-        warn!("could not find a single Player");
         return;
     };
 
@@ -65,13 +105,320 @@ pub fn update_health_bar(
         return;
     };
 
-    heal_bar.width = Val::Percent((health.0 / MAX_PLAYER_HEALTH) as f32 * 100.);
+    heal_bar.width = Val::Percent((health.0 / balance.player.max_health).max(0.) as f32 * 100.);
+}
+
+#[derive(Component)]
+pub struct BossHealthBarContainer;
+
+#[derive(Component)]
+pub struct BossHealthBar;
+
+#[derive(Component)]
+pub struct BossName;
+
+pub fn setup_boss_health_bar(mut commands: Commands) {
+    let container = (
+        BossHealthBarContainer,
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.),
+                top: Val::Px(90.),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+    );
+
+    let name = (
+        BossName,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 20.,
+                color: Color::WHITE,
+                ..default()
+            },
+        ),
+    );
+
+    let frame = NodeBundle {
+        style: Style {
+            width: Val::Px(400.),
+            height: Val::Px(16.),
+            border: UiRect::all(Val::Px(2.)),
+            ..default()
+        },
+        background_color: Color::rgb(0., 0., 0.).into(),
+        ..default()
+    };
+
+    let bar = (
+        BossHealthBar,
+        NodeBundle {
+            style: Style {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                ..default()
+            },
+            background_color: Color::rgb(0.55, 0.1, 0.6).into(),
+            ..default()
+        },
+    );
+
+    commands.spawn((Hud, container)).with_children(|parent| {
+        parent.spawn(name);
+        parent.spawn(frame).with_children(|parent| {
+            parent.spawn(bar);
+        });
+    });
+}
+
+/// Shows the health of the boss the player is fighting, hidden while no boss is engaged.
+pub fn update_boss_health_bar(
+    bosses: Query<(&Boss, &MobKind, &MobAi, &Health)>,
+    mut container_query: Query<&mut Visibility, With<BossHealthBarContainer>>,
+    mut bar_query: Query<&mut Style, With<BossHealthBar>>,
+    mut name_query: Query<&mut Text, With<BossName>>,
+) {
+    let engaged_boss = bosses.iter().find(|(_, _, ai, _)| {
+        !matches!(
+            ai.state,
+            MobState::Idle | MobState::Wander | MobState::GiveUp
+        )
+    });
+
+    for mut visibility in container_query.iter_mut() {
+        *visibility = if engaged_boss.is_some() {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+
+    let Some((boss, kind, _, health)) = engaged_boss else {
+        return;
+    };
+
+    for mut bar in bar_query.iter_mut() {
+        bar.width = Val::Percent((health.0 / boss.max_health).max(0.) as f32 * 100.);
+    }
+
+    for mut text in name_query.iter_mut() {
+        text.sections[0].value = format!("{:?} - phase {}", kind, boss.phase + 1);
+    }
 }
 
-#[derive(PartialEq, Component, Debug, Copy, Clone)]
+#[derive(Component)]
+pub struct CurrencyDisplay;
+
+#[derive(Component)]
+pub struct WoodDisplay;
+
+#[derive(Component)]
+pub struct WaveDisplay;
+
+#[derive(Component)]
+pub struct RejectionMessage(pub Timer);
+
+pub fn setup_currency_display(mut commands: Commands) {
+    commands.spawn((
+        Hud,
+        CurrencyDisplay,
+        TextBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font_size: 20.,
+                    color: Color::GOLD,
+                    ..default()
+                },
+            ),
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(24.),
+                left: Val::Px(2.),
+                ..default()
+            },
+            ..default()
+        },
+    ));
+
+    commands.spawn((
+        Hud,
+        WoodDisplay,
+        TextBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font_size: 20.,
+                    color: Color::rgb(0.7, 0.5, 0.3),
+                    ..default()
+                },
+            ),
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(46.),
+                left: Val::Px(2.),
+                ..default()
+            },
+            ..default()
+        },
+    ));
+
+    commands.spawn((
+        Hud,
+        WaveDisplay,
+        TextBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font_size: 20.,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ),
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(2.),
+                right: Val::Px(4.),
+                ..default()
+            },
+            ..default()
+        },
+    ));
+
+    let message_container = NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.),
+            top: Val::Px(60.),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        ..default()
+    };
+
+    let message = (
+        RejectionMessage(Timer::from_seconds(
+            REJECTION_MESSAGE_SECONDS,
+            TimerMode::Once,
+        )),
+        TextBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font_size: 24.,
+                    color: Color::ORANGE_RED,
+                    ..default()
+                },
+            ),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+    );
+
+    commands
+        .spawn((Hud, message_container))
+        .with_children(|parent| {
+            parent.spawn(message);
+        });
+}
+
+pub fn update_currency_display(
+    currency: Res<Currency>,
+    mut display_query: Query<&mut Text, With<CurrencyDisplay>>,
+) {
+    if !currency.is_changed() {
+        return;
+    }
+
+    for mut text in display_query.iter_mut() {
+        text.sections[0].value = format!("Coins: {}", currency.0);
+    }
+}
+
+pub fn update_wood_display(
+    inventory: Res<Inventory>,
+    mut display_query: Query<&mut Text, With<WoodDisplay>>,
+) {
+    if !inventory.is_changed() {
+        return;
+    }
+
+    for mut text in display_query.iter_mut() {
+        text.sections[0].value = format!("Wood: {}", inventory.wood);
+    }
+}
+
+pub fn update_wave_display(
+    director: Option<Res<WaveDirector>>,
+    mut display_query: Query<&mut Text, With<WaveDisplay>>,
+) {
+    let Some(director) = director else {
+        return;
+    };
+
+    let message = match &director.phase {
+        WavePhase::Loading => String::new(),
+        WavePhase::Calm(timer) => format!("Next wave in {:.0}s", timer.remaining_secs()),
+        WavePhase::Active {
+            definition, killed, ..
+        } => format!(
+            "Wave {}: {}/{}",
+            director.wave, killed, definition.mob_count
+        ),
+    };
+
+    for mut text in display_query.iter_mut() {
+        text.sections[0].value = message.clone();
+    }
+}
+
+pub fn show_rejection_message(
+    mut rejected_events: EventReader<TileActivationRejectedEvent>,
+    mut message_query: Query<(&mut Text, &mut Visibility, &mut RejectionMessage)>,
+) {
+    let Some(event) = rejected_events.read().last() else {
+        return;
+    };
+
+    let message = match event.reason {
+        ActivationRejection::NotAdjacent => "This tile has to border an explored tile".to_string(),
+        ActivationRejection::TooFarFromPlayer { .. } => "Move closer to open this tile".to_string(),
+        ActivationRejection::NotEnoughCurrency { cost, available } => {
+            format!("Not enough coins to open this tile ({available}/{cost})")
+        }
+    };
+
+    for (mut text, mut visibility, mut timer) in message_query.iter_mut() {
+        text.sections[0].value = message.clone();
+        *visibility = Visibility::Visible;
+        timer.0.reset();
+    }
+}
+
+pub fn hide_rejection_message(
+    time: Res<Time>,
+    mut message_query: Query<(&mut Visibility, &mut RejectionMessage)>,
+) {
+    for (mut visibility, mut timer) in message_query.iter_mut() {
+        if timer.0.tick(time.delta()).just_finished() {
+            *visibility = Visibility::Hidden;
+        }
+    }
+}
+
+#[derive(PartialEq, Component, Debug, Copy, Clone, Serialize, Deserialize)]
 pub enum AttackType {
     Melee,
     Fireball,
+    /// reward for beating the first boss
+    Frostbolt,
 }
 
 #[derive(Component)]
@@ -85,7 +432,14 @@ pub struct SelectedSlot {
     pub index: usize,
 }
 
-fn setup(mut commands: Commands) {
+/// Materials the player has gathered.
+#[derive(Resource, Default)]
+pub struct Inventory {
+    pub wood: u32,
+}
+
+/// Inventory bar with the starting attacks of a run.
+pub fn setup(mut commands: Commands) {
     let container = NodeBundle {
         style: Style {
             width: Val::Percent(100.),
@@ -110,26 +464,26 @@ fn setup(mut commands: Commands) {
         ..Default::default()
     };
 
-    commands.spawn(container).with_children(|parent| {
-        parent.spawn(inventory_slot.clone()).insert(InventorySlot {
-            index: 0,
-            attack: Some(AttackType::Melee),
-        });
-
-        parent.spawn(inventory_slot.clone()).insert(InventorySlot {
-            index: 1,
-            attack: Some(AttackType::Fireball),
-        });
-
-        for i in 2..5 {
+    commands.spawn((Hud, container)).with_children(|parent| {
+        for i in 0..INVENTORY_SLOTS {
             parent.spawn(inventory_slot.clone()).insert(InventorySlot {
                 index: i,
-                attack: None,
+                attack: starting_attack(i),
             });
         }
     });
 
     commands.insert_resource(SelectedSlot { index: 0 });
+    commands.insert_resource(Inventory::default());
+}
+
+/// Attack in the given inventory slot at the start of a run.
+fn starting_attack(index: usize) -> Option<AttackType> {
+    match index {
+        0 => Some(AttackType::Melee),
+        1 => Some(AttackType::Fireball),
+        _ => None,
+    }
 }
 
 fn select_inventory_slot_keyboard(
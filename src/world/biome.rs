@@ -0,0 +1,119 @@
+use crate::entities::mob_kind::MobKind;
+use crate::world::grid::TileGrid;
+use crate::world::seed::WorldSeed;
+use crate::world::TILE_SIZE;
+use bevy::prelude::*;
+use noise::{Fbm, NoiseFn, Perlin};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// How many tiles one noise unit spans, larger values give larger biomes.
+pub static BIOME_SCALE: f32 = 4.;
+
+// perlin noise is always zero on integer coordinates, so move the samples off the lattice
+static NOISE_OFFSET: f64 = 0.37;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash, Component, Serialize, Deserialize)]
+pub enum Biome {
+    Forest,
+    PineForest,
+    Clearing,
+    FrozenLake,
+    RockyRidge,
+}
+
+pub static BIOMES: [Biome; 5] = [
+    Biome::Forest,
+    Biome::PineForest,
+    Biome::Clearing,
+    Biome::FrozenLake,
+    Biome::RockyRidge,
+];
+
+pub struct BiomeDefinition {
+    /// tint applied to every ground sprite
+    pub ground_color: Color,
+    /// indices into the forest ground sprite sheet this biome picks from
+    pub ground_variants: Range<usize>,
+    /// multiplier for how many mobs emerge from tiles of this biome
+    pub mob_spawn_rate: f32,
+    /// mob kinds emerging from tiles of this biome with their relative weights
+    pub mobs: &'static [(MobKind, f32)],
+}
+
+impl Biome {
+    pub fn definition(&self) -> BiomeDefinition {
+        match self {
+            Biome::Forest => BiomeDefinition {
+                ground_color: Color::WHITE,
+                ground_variants: 0..9,
+                mob_spawn_rate: 1.,
+                mobs: &[(MobKind::Wolf, 3.), (MobKind::Wisp, 1.)],
+            },
+            Biome::PineForest => BiomeDefinition {
+                ground_color: Color::rgb(0.75, 0.85, 0.8),
+                ground_variants: 0..4,
+                mob_spawn_rate: 1.5,
+                mobs: &[(MobKind::Wolf, 1.)],
+            },
+            Biome::Clearing => BiomeDefinition {
+                ground_color: Color::rgb(1., 1., 0.9),
+                ground_variants: 0..9,
+                mob_spawn_rate: 0.5,
+                mobs: &[(MobKind::Wolf, 1.), (MobKind::Wisp, 1.)],
+            },
+            Biome::FrozenLake => BiomeDefinition {
+                ground_color: Color::rgb(0.7, 0.85, 1.),
+                ground_variants: 0..1,
+                mob_spawn_rate: 0.75,
+                mobs: &[(MobKind::Wisp, 2.), (MobKind::IceGolem, 1.)],
+            },
+            Biome::RockyRidge => BiomeDefinition {
+                ground_color: Color::rgb(0.65, 0.65, 0.7),
+                ground_variants: 0..2,
+                mob_spawn_rate: 1.25,
+                mobs: &[(MobKind::IceGolem, 2.), (MobKind::Wolf, 1.)],
+            },
+        }
+    }
+}
+
+/// Coherent noise over the whole world, neighbouring tiles end up in the same or related biomes.
+#[derive(Resource)]
+pub struct BiomeMap {
+    moisture: Fbm<Perlin>,
+    elevation: Fbm<Perlin>,
+}
+
+impl BiomeMap {
+    pub fn new(seed: &WorldSeed) -> Self {
+        BiomeMap {
+            moisture: Fbm::new(seed.0 as u32),
+            elevation: Fbm::new((seed.0 >> 32) as u32),
+        }
+    }
+
+    pub fn biome_at(&self, world_position: Vec2) -> Biome {
+        let point = world_position / (TILE_SIZE * BIOME_SCALE);
+        let point = [point.x as f64 + NOISE_OFFSET, point.y as f64 + NOISE_OFFSET];
+
+        let elevation = self.elevation.get(point);
+        let moisture = self.moisture.get(point);
+
+        if elevation > 0.35 {
+            Biome::RockyRidge
+        } else if moisture < -0.35 {
+            Biome::FrozenLake
+        } else if moisture > 0.25 {
+            Biome::PineForest
+        } else if moisture < -0.15 {
+            Biome::Clearing
+        } else {
+            Biome::Forest
+        }
+    }
+
+    pub fn tile_biome(&self, position: IVec2) -> Biome {
+        self.biome_at(TileGrid::grid_to_world(position))
+    }
+}
@@ -0,0 +1,125 @@
+use crate::world::{Tile, TILE_SIZE};
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+
+pub static NEIGHBOUR_OFFSETS: [IVec2; 8] = [
+    IVec2::new(-1, -1),
+    IVec2::new(0, -1),
+    IVec2::new(1, -1),
+    IVec2::new(-1, 0),
+    IVec2::new(1, 0),
+    IVec2::new(-1, 1),
+    IVec2::new(0, 1),
+    IVec2::new(1, 1),
+];
+
+pub static EDGE_OFFSETS: [IVec2; 4] = [
+    IVec2::new(0, -1),
+    IVec2::new(-1, 0),
+    IVec2::new(1, 0),
+    IVec2::new(0, 1),
+];
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum TileState {
+    Closed,
+    Open,
+}
+
+/// Everything that happened to a tile since it was generated from the seed.
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
+pub struct TileChanges {
+    pub felled_trees: Vec<UVec2>,
+    /// whether the boss guarding this lair tile was beaten
+    #[serde(default)]
+    pub boss_defeated: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct GridTile {
+    pub entity: Entity,
+    pub state: TileState,
+    /// whether the content of an open tile is currently spawned
+    pub loaded: bool,
+    pub changes: TileChanges,
+}
+
+/// Source of truth for which tile lives at which grid position.
+#[derive(Resource, Default)]
+pub struct TileGrid {
+    tiles: HashMap<IVec2, GridTile>,
+}
+
+impl TileGrid {
+    pub fn get(&self, position: IVec2) -> Option<&GridTile> {
+        self.tiles.get(&position)
+    }
+
+    pub fn get_mut(&mut self, position: IVec2) -> Option<&mut GridTile> {
+        self.tiles.get_mut(&position)
+    }
+
+    pub fn state(&self, position: IVec2) -> Option<TileState> {
+        self.get(position).map(|tile| tile.state)
+    }
+
+    pub fn contains(&self, position: IVec2) -> bool {
+        self.tiles.contains_key(&position)
+    }
+
+    pub fn insert(&mut self, position: IVec2, entity: Entity, state: TileState) {
+        self.tiles.insert(
+            position,
+            GridTile {
+                entity,
+                state,
+                loaded: false,
+                changes: TileChanges::default(),
+            },
+        );
+    }
+
+    pub fn remove(&mut self, position: IVec2) -> Option<GridTile> {
+        self.tiles.remove(&position)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (IVec2, &GridTile)> {
+        self.tiles.iter().map(|(position, tile)| (*position, tile))
+    }
+
+    /// All eight surrounding positions, including diagonals.
+    pub fn neighbours(position: IVec2) -> impl Iterator<Item = IVec2> {
+        NEIGHBOUR_OFFSETS
+            .iter()
+            .map(move |offset| position + *offset)
+    }
+
+    /// The four positions sharing an edge with the given one.
+    pub fn edge_neighbours(position: IVec2) -> impl Iterator<Item = IVec2> {
+        EDGE_OFFSETS.iter().map(move |offset| position + *offset)
+    }
+
+    pub fn world_to_grid(position: Vec2) -> IVec2 {
+        // tiles are centered on their grid position, so round instead of truncating
+        (position / TILE_SIZE).round().as_ivec2()
+    }
+
+    pub fn grid_to_world(position: IVec2) -> Vec2 {
+        position.as_vec2() * TILE_SIZE
+    }
+}
+
+pub fn remove_despawned_tiles(
+    mut grid: ResMut<TileGrid>,
+    mut removed_tiles: RemovedComponents<Tile>,
+) {
+    let removed: HashSet<Entity> = removed_tiles.read().collect();
+
+    // touching the grid without a removed tile would still mark it as changed
+    if removed.is_empty() {
+        return;
+    }
+
+    grid.tiles.retain(|_, tile| !removed.contains(&tile.entity));
+}
@@ -0,0 +1,100 @@
+use crate::world::{ForestGroundAssets, SUB_TILES};
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+
+/// Size of one sprite in the ground sprite sheet in pixels.
+pub static GROUND_SPRITE_PIXELS: usize = 16;
+
+/// Ground of a tile that still has to be baked into a single texture.
+/// Holds the sprite sheet index and tint of every sub tile, column by column.
+#[derive(Component)]
+pub struct PendingGround {
+    pub sub_tiles: Vec<(usize, Color)>,
+}
+
+pub fn bake_tile_ground(
+    mut commands: Commands,
+    pending_grounds: Query<(Entity, &PendingGround)>,
+    ground_assets: Res<ForestGroundAssets>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    if pending_grounds.is_empty() {
+        return;
+    }
+
+    // the sprite sheet might still be loading, try again next frame
+    let Some(source) = images.get(ground_assets.texture().id()) else {
+        return;
+    };
+
+    let converted;
+    let source = if source.texture_descriptor.format == TextureFormat::Rgba8UnormSrgb {
+        source
+    } else {
+        let Some(image) = source.convert(TextureFormat::Rgba8UnormSrgb) else {
+            error!("could not convert the forest ground texture to rgba");
+            return;
+        };
+
+        converted = image;
+        &converted
+    };
+
+    let baked_grounds = pending_grounds
+        .iter()
+        .map(|(entity, pending_ground)| (entity, bake_ground(source, &pending_ground.sub_tiles)))
+        .collect::<Vec<_>>();
+
+    for (entity, data) in baked_grounds {
+        let size = (SUB_TILES as usize * GROUND_SPRITE_PIXELS) as u32;
+
+        let image = Image::new(
+            Extent3d {
+                width: size,
+                height: size,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            data,
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::RENDER_WORLD,
+        );
+
+        commands
+            .entity(entity)
+            .insert((images.add(image), Visibility::Inherited))
+            .remove::<PendingGround>();
+    }
+}
+
+fn bake_ground(source: &Image, sub_tiles: &[(usize, Color)]) -> Vec<u8> {
+    let sub_tiles_per_side = SUB_TILES as usize;
+    let size = sub_tiles_per_side * GROUND_SPRITE_PIXELS;
+    let source_width = source.width() as usize;
+
+    let mut data = vec![0; size * size * 4];
+
+    for (i, (index, color)) in sub_tiles.iter().enumerate() {
+        let x = i / sub_tiles_per_side;
+        // world y grows upwards while image rows grow downwards
+        let y = sub_tiles_per_side - 1 - i % sub_tiles_per_side;
+        let tint = color.as_rgba_f32();
+
+        for pixel_y in 0..GROUND_SPRITE_PIXELS {
+            for pixel_x in 0..GROUND_SPRITE_PIXELS {
+                let from = (pixel_y * source_width + index * GROUND_SPRITE_PIXELS + pixel_x) * 4;
+                let to = ((y * GROUND_SPRITE_PIXELS + pixel_y) * size
+                    + x * GROUND_SPRITE_PIXELS
+                    + pixel_x)
+                    * 4;
+
+                for channel in 0..4 {
+                    data[to + channel] = (source.data[from + channel] as f32 * tint[channel]) as u8;
+                }
+            }
+        }
+    }
+
+    data
+}
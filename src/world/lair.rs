@@ -0,0 +1,63 @@
+use crate::entities::mob_kind::MobKind;
+use crate::world::seed::WorldSeed;
+use crate::world::{CloseTile, Tile, TILE_SIZE};
+use bevy::math::vec2;
+use bevy::prelude::*;
+
+/// Tiles closer to the start than this never hold a lair.
+pub static LAIR_MIN_DISTANCE: i32 = 3;
+/// Roughly one in this many tiles is a lair.
+pub static LAIR_CHANCE: u64 = 12;
+/// Bosses that can guard a lair, picked per tile from the seed.
+pub static LAIR_BOSSES: &[MobKind] = &[MobKind::Yeti];
+
+// keeps the lair roll independent of the tile layout rng
+static LAIR_SALT: u64 = 0x1A1F_B055;
+
+/// Marks closed lair tiles so the player knows what they are about to open.
+#[derive(Component)]
+pub struct LairMarker;
+
+/// Boss guarding the tile at the given position, `None` if it is no lair.
+pub fn lair_boss(seed: &WorldSeed, position: IVec2) -> Option<MobKind> {
+    if position.abs().max_element() < LAIR_MIN_DISTANCE {
+        return None;
+    }
+
+    let roll = WorldSeed(seed.0 ^ LAIR_SALT).tile_seed(position);
+
+    if roll % LAIR_CHANCE != 0 {
+        return None;
+    }
+
+    let index = (roll / LAIR_CHANCE) as usize % LAIR_BOSSES.len();
+
+    Some(LAIR_BOSSES[index])
+}
+
+pub fn mark_lair_tiles(
+    mut commands: Commands,
+    seed: Res<WorldSeed>,
+    closed_tiles: Query<(Entity, &Tile), Added<CloseTile>>,
+) {
+    for (entity, tile) in closed_tiles.iter() {
+        if lair_boss(&seed, tile.position).is_none() {
+            continue;
+        }
+
+        commands.entity(entity).with_children(|parent| {
+            parent.spawn((
+                LairMarker,
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::rgb(0.3, 0.05, 0.35),
+                        custom_size: Some(vec2(TILE_SIZE / 3., TILE_SIZE / 3.)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(Vec3::new(0., 0., 0.5)),
+                    ..default()
+                },
+            ));
+        });
+    }
+}
@@ -0,0 +1,616 @@
+use crate::balance::GameBalance;
+use crate::entities::data::{AttackableFrom, EntityType, Health, Player};
+use crate::entities::entity::deal_damage;
+use crate::{AppState, PhysicsLayers};
+use bevy::math::vec2;
+use bevy::prelude::*;
+use bevy_xpbd_2d::prelude::*;
+use biome::BiomeMap;
+use frozen_forest_macro::sprite_sheet;
+use grid::{remove_despawned_tiles, TileChanges, TileGrid, TileState};
+use ground::{bake_tile_ground, PendingGround};
+use iter_tools::Itertools;
+use lair::mark_lair_tiles;
+use nav::{update_flow_field, update_nav_grid, update_nav_obstacles, FlowField, NavGrid};
+use rand::Rng;
+use seed::WorldSeed;
+use std::ops::Range;
+use streaming::stream_tiles;
+use trees::{fell_trees, spawn_stump, spawn_tree};
+use unlock::{
+    check_unlock, reward_mob_kills, unlock_cost, Currency, TileActivationRejectedEvent,
+    UnlockCostLabel, UnlockRules,
+};
+
+pub mod biome;
+pub mod grid;
+pub mod ground;
+pub mod lair;
+pub mod nav;
+pub mod seed;
+pub mod streaming;
+pub mod trees;
+pub mod unlock;
+
+pub struct WorldPlugin;
+
+impl Plugin for WorldPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TileGrid>()
+            .init_resource::<Currency>()
+            .init_resource::<UnlockRules>()
+            .init_resource::<NavGrid>()
+            .init_resource::<FlowField>()
+            .add_systems(PreStartup, load_assets)
+            .add_systems(OnExit(AppState::Menu), start_world)
+            .add_systems(OnEnter(AppState::Menu), teardown_world)
+            .add_systems(
+                Update,
+                (
+                    hover_tile,
+                    (highlight_hovered_tiles, activate_hovered_tiles),
+                    create_surrounding_tiles,
+                    activate_tiles,
+                    stream_tiles,
+                    bake_tile_ground,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Playing)),
+            )
+            .add_systems(
+                Update,
+                (reward_mob_kills, fell_trees)
+                    .after(deal_damage)
+                    .run_if(in_state(AppState::Playing)),
+            )
+            .add_systems(
+                Update,
+                (update_nav_grid, update_nav_obstacles, update_flow_field)
+                    .chain()
+                    .after(activate_tiles)
+                    .after(fell_trees)
+                    .run_if(in_state(AppState::Playing)),
+            )
+            .add_systems(Update, mark_lair_tiles.run_if(in_state(AppState::Playing)))
+            .add_systems(PostUpdate, remove_despawned_tiles)
+            .add_event::<HoverTileEvent>()
+            .add_event::<ActivateTileEvent>()
+            .add_event::<TileActivationRejectedEvent>();
+    }
+}
+
+pub static SUB_TILES: f32 = 15.;
+pub static SUB_TILE_SIZE: f32 = 16.;
+pub static TILE_SIZE: f32 = SUB_TILES * SUB_TILE_SIZE;
+
+pub static TREE_SPRITE_SIZE: f32 = 16.;
+/// Number of sprites in `tree.png`, has to match the count of `TreeAssets`.
+pub static TREE_VARIANTS: usize = 4;
+pub static ROCK_SIZE: f32 = 12.;
+
+#[derive(Event)]
+pub struct HoverTileEvent(Entity);
+
+#[derive(Event)]
+pub struct ActivateTileEvent(Entity);
+
+#[derive(Component)]
+pub struct Tile {
+    pub position: IVec2,
+}
+
+#[derive(Component)]
+pub struct CloseTile;
+
+#[derive(Component)]
+pub struct Rock;
+
+#[sprite_sheet(count = 9, path = "forest-ground.png")]
+pub struct ForestGroundAssets {}
+
+#[sprite_sheet(count = 4, path = "tree.png")]
+pub struct TreeAssets {}
+
+fn load_assets(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    commands.insert_resource(ForestGroundAssets::load(
+        &asset_server,
+        &mut texture_atlases,
+    ));
+    commands.insert_resource(TreeAssets::load(&asset_server, &mut texture_atlases));
+}
+
+/// Generates a new world for the run, `load_save` replaces it afterwards if there is a save.
+pub fn start_world(
+    mut commands: Commands,
+    mut grid: ResMut<TileGrid>,
+    mut currency: ResMut<Currency>,
+    balance: Res<GameBalance>,
+) {
+    let seed = WorldSeed::from_env();
+    let biome_map = BiomeMap::new(&seed);
+
+    info!("world seed: {}", seed.0);
+
+    *grid = TileGrid::default();
+    *currency = Currency(balance.unlock.starting_currency);
+
+    commands.spawn((
+        Health(20.),
+        EntityType::Wall,
+        AttackableFrom(vec![EntityType::Mob]),
+        RigidBody::Static,
+        Collider::rectangle(100., 100.),
+        CollisionLayers::new([PhysicsLayers::Mob, PhysicsLayers::Entity], LayerMask::ALL),
+        Restitution::new(0.),
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgb(0.4, 0.1, 0.5),
+                custom_size: Some(vec2(100., 100.)),
+                ..default()
+            },
+            transform: Transform::from_translation(Vec3::new(100., 100., 0.)),
+            ..default()
+        },
+    ));
+
+    spawn_start_tiles(&mut commands, &mut grid, &biome_map);
+
+    commands.insert_resource(seed);
+    commands.insert_resource(biome_map);
+}
+
+/// Open tiles around the origin, surrounded by a ring of closed ones.
+fn spawn_start_tiles(commands: &mut Commands, grid: &mut TileGrid, biome_map: &BiomeMap) {
+    for x in -2..=2 {
+        for y in -2..=2 {
+            let position = IVec2::new(x, y);
+
+            if x.abs() == 2 || y.abs() == 2 {
+                closed_tile(commands, grid, position);
+            } else {
+                open_tile(commands, grid, biome_map, position);
+            }
+        }
+    }
+}
+
+fn teardown_world(
+    mut commands: Commands,
+    mut grid: ResMut<TileGrid>,
+    mut nav_grid: ResMut<NavGrid>,
+    mut flow_field: ResMut<FlowField>,
+    tiles: Query<Entity, With<Tile>>,
+    walls: Query<(Entity, &EntityType)>,
+) {
+    for tile in tiles.iter() {
+        commands.entity(tile).despawn_recursive();
+    }
+
+    for (wall, _) in walls
+        .iter()
+        .filter(|(_, entity_type)| **entity_type == EntityType::Wall)
+    {
+        commands.entity(wall).despawn_recursive();
+    }
+
+    *grid = TileGrid::default();
+    *nav_grid = NavGrid::default();
+    *flow_field = FlowField::default();
+}
+
+pub fn open_tile(
+    commands: &mut Commands,
+    grid: &mut TileGrid,
+    biome_map: &BiomeMap,
+    position: IVec2,
+) {
+    let world_position = TileGrid::grid_to_world(position);
+
+    // the content is spawned by `stream_tiles` once the tile is close to the camera
+    let tile = commands
+        .spawn((
+            Tile { position },
+            biome_map.tile_biome(position),
+            SpatialBundle {
+                transform: Transform::from_translation(world_position.extend(-5.)),
+                ..default()
+            },
+        ))
+        .id();
+
+    grid.insert(position, tile, TileState::Open);
+}
+
+/// What generates on an open tile before any changes, only depends on the seed and the position.
+pub struct TileLayout {
+    /// ground sprite index and tint per sub tile
+    pub ground: Vec<(usize, Color)>,
+    /// sub tile and sprite index of every tree
+    pub trees: Vec<(UVec2, usize)>,
+    pub rocks: Vec<UVec2>,
+}
+
+pub fn tile_layout(
+    position: IVec2,
+    seed: &WorldSeed,
+    biome_map: &BiomeMap,
+    balance: &GameBalance,
+) -> TileLayout {
+    let mut rng = seed.tile_rng(position);
+
+    let biome = biome_map.tile_biome(position);
+    let biome_balance = balance.biome(biome);
+
+    let world_position = TileGrid::grid_to_world(position);
+
+    let mut ground = vec![];
+
+    for x in 0..SUB_TILES as u32 {
+        for y in 0..SUB_TILES as u32 {
+            let offset = sub_tile_offset(UVec2::new(x, y));
+
+            // sample the biome per sub tile so the ground blends into the neighbouring tiles
+            let definition = biome_map.biome_at(world_position + offset).definition();
+
+            ground.push((
+                rng.gen_range(definition.ground_variants),
+                definition.ground_color,
+            ));
+        }
+    }
+
+    let mut occupied_positions = vec![];
+    let mut trees = vec![];
+    let mut rocks = vec![];
+
+    for _ in 0..roll(&mut rng, biome_balance.trees.clone()) {
+        let sub_tile = random_sub_tile(&mut rng);
+        let variant = rng.gen_range(0..TREE_VARIANTS);
+
+        if occupied_positions.contains(&sub_tile)
+            || balance
+                .biome(biome_map.biome_at(world_position + sub_tile_offset(sub_tile)))
+                .trees
+                .is_empty()
+        {
+            continue;
+        }
+
+        occupied_positions.push(sub_tile);
+        trees.push((sub_tile, variant));
+    }
+
+    for _ in 0..roll(&mut rng, biome_balance.rocks.clone()) {
+        let sub_tile = random_sub_tile(&mut rng);
+
+        if occupied_positions.contains(&sub_tile) {
+            continue;
+        }
+
+        occupied_positions.push(sub_tile);
+        rocks.push(sub_tile);
+    }
+
+    TileLayout {
+        ground,
+        trees,
+        rocks,
+    }
+}
+
+/// Spawns the ground, trees and rocks of an open tile as children of the tile entity.
+/// The layout comes from `tile_layout`, changes are applied on top of it.
+pub fn populate_tile(
+    commands: &mut Commands,
+    tile: Entity,
+    layout: TileLayout,
+    changes: &TileChanges,
+    tree_assets: &TreeAssets,
+    tree_health: f64,
+) {
+    commands.entity(tile).with_children(|parent| {
+        parent.spawn((
+            PendingGround {
+                sub_tiles: layout.ground,
+            },
+            SpriteBundle {
+                sprite: Sprite {
+                    custom_size: Some(vec2(TILE_SIZE, TILE_SIZE)),
+                    ..default()
+                },
+                transform: Transform::from_translation(Vec3::new(0., 0., -5.)),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+        ));
+
+        for (sub_tile, variant) in layout.trees {
+            if changes.felled_trees.contains(&sub_tile) {
+                spawn_stump(parent, sub_tile);
+            } else {
+                spawn_tree(
+                    parent,
+                    sub_tile,
+                    tree_assets.atlas_with_index(variant),
+                    tree_assets.texture(),
+                    tree_health,
+                );
+            }
+        }
+
+        for sub_tile in layout.rocks {
+            parent.spawn((
+                Rock,
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::rgb(0.45, 0.45, 0.5),
+                        custom_size: Some(vec2(ROCK_SIZE, ROCK_SIZE)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(sub_tile_offset(sub_tile).extend(3.)),
+                    ..default()
+                },
+                RigidBody::Static,
+                Collider::rectangle(ROCK_SIZE, ROCK_SIZE),
+                CollisionLayers::new(PhysicsLayers::Wall, LayerMask::ALL),
+                Restitution::new(0.),
+            ));
+        }
+    });
+}
+
+/// Offset of a sub tile center from the center of its tile.
+pub fn sub_tile_offset(sub_tile: UVec2) -> Vec2 {
+    //offset so 0/0 is in the center of the tile
+    (sub_tile.as_vec2() - SUB_TILES / 2.) * SUB_TILE_SIZE + SUB_TILE_SIZE / 2.
+}
+
+fn random_sub_tile(rng: &mut impl Rng) -> UVec2 {
+    UVec2::new(
+        rng.gen_range(0..SUB_TILES as u32),
+        rng.gen_range(0..SUB_TILES as u32),
+    )
+}
+
+fn roll(rng: &mut impl Rng, range: Range<usize>) -> usize {
+    if range.is_empty() {
+        return 0;
+    }
+
+    rng.gen_range(range)
+}
+
+pub fn closed_tile(commands: &mut Commands, grid: &mut TileGrid, position: IVec2) {
+    let world_position = TileGrid::grid_to_world(position);
+
+    let tile = commands
+        .spawn((
+            Tile { position },
+            CloseTile,
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgb(1.0, 0.2, 0.1),
+                    custom_size: Some(vec2(TILE_SIZE, TILE_SIZE)),
+                    ..default()
+                },
+                transform: Transform::from_translation(world_position.extend(-5.)),
+                ..default()
+            },
+            RigidBody::Static,
+            Collider::rectangle(TILE_SIZE, TILE_SIZE),
+            CollisionLayers::new(PhysicsLayers::ClosedTile, LayerMask::ALL),
+            Restitution::new(0.),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                UnlockCostLabel,
+                Text2dBundle {
+                    // filled in by `highlight_hovered_tiles`, the cost follows the balance
+                    text: Text::from_section(
+                        String::new(),
+                        TextStyle {
+                            font_size: 24.,
+                            color: Color::WHITE,
+                            ..default()
+                        },
+                    ),
+                    transform: Transform::from_translation(Vec3::new(0., 0., 1.)),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+            ));
+        })
+        .id();
+
+    grid.insert(position, tile, TileState::Closed);
+}
+
+fn hover_tile(
+    windows: Query<&Window>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<Camera>>,
+    grid: Res<TileGrid>,
+    mut hover_tile_event: EventWriter<HoverTileEvent>,
+) {
+    let window = windows.single();
+    let (camera, camera_transform) = camera_query.single();
+
+    let Some(cursor_position) = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor))
+    else {
+        return;
+    };
+
+    if let Some(tile) = grid.get(TileGrid::world_to_grid(cursor_position)) {
+        if tile.state == TileState::Closed {
+            hover_tile_event.send(HoverTileEvent(tile.entity));
+        }
+    }
+}
+
+fn highlight_hovered_tiles(
+    mut hover_tile_event: EventReader<HoverTileEvent>,
+    mut closed_tiles: Query<(Entity, &Tile, &mut Sprite, &Children), With<CloseTile>>,
+    mut cost_labels: Query<(&mut Visibility, &mut Text), With<UnlockCostLabel>>,
+    currency: Res<Currency>,
+    balance: Res<GameBalance>,
+) {
+    let hovered_entities = hover_tile_event.read().map(|event| event.0).collect_vec();
+
+    for (entity, tile, mut sprite, children) in closed_tiles.iter_mut() {
+        let hovered = hovered_entities.contains(&entity);
+        let cost = unlock_cost(tile.position, &balance.unlock);
+        let affordable = currency.0 >= cost;
+
+        sprite.color = match (hovered, affordable) {
+            (true, true) => Color::GOLD,
+            (true, false) => Color::MAROON,
+            (false, _) => Color::RED,
+        };
+
+        let mut labels = cost_labels.iter_many_mut(children);
+
+        while let Some((mut visibility, mut text)) = labels.fetch_next() {
+            text.sections[0].value = cost.to_string();
+            *visibility = if hovered {
+                Visibility::Visible
+            } else {
+                Visibility::Hidden
+            };
+
+            text.sections[0].style.color = if affordable {
+                Color::WHITE
+            } else {
+                Color::ORANGE_RED
+            };
+        }
+    }
+}
+
+fn activate_hovered_tiles(
+    mut hover_tile_event: EventReader<HoverTileEvent>,
+    mut activate_tile_event: EventWriter<ActivateTileEvent>,
+    mut rejected_event: EventWriter<TileActivationRejectedEvent>,
+    mouse_button_input: Res<ButtonInput<MouseButton>>,
+    closed_tiles: Query<&Tile, With<CloseTile>>,
+    player_query: Query<&Transform, With<Player>>,
+    grid: Res<TileGrid>,
+    rules: Res<UnlockRules>,
+    mut currency: ResMut<Currency>,
+    balance: Res<GameBalance>,
+) {
+    if !mouse_button_input.just_pressed(MouseButton::Left) {
+        hover_tile_event.clear();
+        return;
+    };
+
+    for event in hover_tile_event.read() {
+        let Ok(tile) = closed_tiles.get(event.0) else {
+            continue;
+        };
+
+        let player_position = player_query
+            .get_single()
+            .ok()
+            .map(|transform| transform.translation.xy());
+
+        let cost = match check_unlock(
+            tile.position,
+            &grid,
+            &rules,
+            &balance.unlock,
+            player_position,
+            &currency,
+        ) {
+            Ok(cost) => cost,
+            Err(reason) => {
+                rejected_event.send(TileActivationRejectedEvent {
+                    tile: event.0,
+                    reason,
+                });
+                continue;
+            }
+        };
+
+        currency.0 -= cost;
+        activate_tile_event.send(ActivateTileEvent(event.0));
+    }
+}
+
+fn activate_tiles(
+    mut commands: Commands,
+    mut grid: ResMut<TileGrid>,
+    mut activate_tile_event: EventReader<ActivateTileEvent>,
+    closed_tiles: Query<&Tile, With<CloseTile>>,
+    biome_map: Res<BiomeMap>,
+) {
+    for event in activate_tile_event.read() {
+        let Ok(tile) = closed_tiles.get(event.0) else {
+            continue;
+        };
+
+        if grid.state(tile.position) != Some(TileState::Closed) {
+            continue;
+        }
+
+        commands.entity(event.0).despawn_recursive();
+        open_tile(&mut commands, &mut grid, &biome_map, tile.position)
+    }
+}
+
+fn create_surrounding_tiles(
+    mut commands: Commands,
+    mut grid: ResMut<TileGrid>,
+    mut activate_tile_event: EventReader<ActivateTileEvent>,
+    tiles: Query<&Tile>,
+) {
+    for event in activate_tile_event.read() {
+        let Ok(tile) = tiles.get(event.0) else {
+            continue;
+        };
+
+        for neighbour in TileGrid::neighbours(tile.position) {
+            if !grid.contains(neighbour) {
+                closed_tile(&mut commands, &mut grid, neighbour)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn balance() -> GameBalance {
+        ron::from_str(include_str!("../../assets/balance.ron")).unwrap()
+    }
+
+    #[test]
+    fn tile_layout_only_depends_on_seed_and_position() {
+        let balance = balance();
+
+        for position in [IVec2::ZERO, IVec2::new(3, -2), IVec2::new(-7, 11)] {
+            let [first, second] = [WorldSeed(42), WorldSeed(42)]
+                .map(|seed| tile_layout(position, &seed, &BiomeMap::new(&seed), &balance));
+
+            assert_eq!(first.ground, second.ground);
+            assert_eq!(first.trees, second.trees);
+            assert_eq!(first.rocks, second.rocks);
+        }
+    }
+
+    #[test]
+    fn tile_layout_changes_with_the_seed() {
+        let balance = balance();
+        let position = IVec2::new(1, 1);
+
+        let [first, second] = [WorldSeed(1), WorldSeed(2)]
+            .map(|seed| tile_layout(position, &seed, &BiomeMap::new(&seed), &balance));
+
+        assert_ne!(first.ground, second.ground);
+    }
+}
@@ -0,0 +1,362 @@
+use crate::balance::GameBalance;
+use crate::entities::data::Player;
+use crate::world::biome::BiomeMap;
+use crate::world::grid::{TileGrid, TileState, NEIGHBOUR_OFFSETS};
+use crate::world::seed::WorldSeed;
+use crate::world::trees::Tree;
+use crate::world::{tile_layout, Rock, Tile, SUB_TILES, SUB_TILE_SIZE};
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+use bevy_xpbd_2d::prelude::*;
+use iter_tools::Itertools;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// The flow field only covers cells within this many cells of the player,
+/// mobs further away walk straight towards the player.
+pub static FLOW_FIELD_RADIUS: i32 = 40;
+/// Seconds between two flow field updates.
+pub static FLOW_FIELD_INTERVAL: f32 = 0.25;
+
+static STRAIGHT_COST: u32 = 10;
+static DIAGONAL_COST: u32 = 14;
+
+/// Walkability of every sub tile sized cell, built from the tile layouts and the static colliders
+/// placed on top of them. Cells outside of open tiles are never walkable.
+#[derive(Resource, Default)]
+pub struct NavGrid {
+    tiles: HashMap<IVec2, NavTile>,
+    /// cells covered by static colliders that aren't part of a tile layout, like the wall
+    obstacles: HashSet<IVec2>,
+}
+
+struct NavTile {
+    walkable: Vec<bool>,
+    /// number of felled trees when the tile was built, to notice newly felled ones
+    felled_trees: usize,
+}
+
+impl NavGrid {
+    pub fn world_to_cell(position: Vec2) -> IVec2 {
+        (position / SUB_TILE_SIZE).round().as_ivec2()
+    }
+
+    pub fn cell_to_world(cell: IVec2) -> Vec2 {
+        cell.as_vec2() * SUB_TILE_SIZE
+    }
+
+    /// Tile position and sub tile of a cell.
+    fn cell_tile(cell: IVec2) -> (IVec2, UVec2) {
+        let sub_tiles = SUB_TILES as i32;
+        // the center cell of a tile sits on the tile position
+        let shifted = cell + sub_tiles / 2;
+
+        (
+            shifted.div_euclid(IVec2::splat(sub_tiles)),
+            shifted.rem_euclid(IVec2::splat(sub_tiles)).as_uvec2(),
+        )
+    }
+
+    fn sub_tile_index(sub_tile: UVec2) -> usize {
+        (sub_tile.y * SUB_TILES as u32 + sub_tile.x) as usize
+    }
+
+    /// Every cell the box overlaps, even if only partly, so mobs don't try to squeeze past its edge.
+    fn covered_cells(min: Vec2, max: Vec2) -> impl Iterator<Item = IVec2> {
+        let first = (min / SUB_TILE_SIZE + 0.5).floor().as_ivec2();
+        let last = (max / SUB_TILE_SIZE - 0.5).ceil().as_ivec2();
+
+        (first.x..=last.x)
+            .cartesian_product(first.y..=last.y)
+            .map(|(x, y)| IVec2::new(x, y))
+    }
+
+    pub fn is_walkable(&self, cell: IVec2) -> bool {
+        if self.obstacles.contains(&cell) {
+            return false;
+        }
+
+        let (tile, sub_tile) = NavGrid::cell_tile(cell);
+
+        self.tiles
+            .get(&tile)
+            .is_some_and(|tile| tile.walkable[NavGrid::sub_tile_index(sub_tile)])
+    }
+
+    /// Walkable neighbours of a cell with the cost to step there.
+    /// Diagonal steps are only allowed if they don't cut a blocked corner.
+    pub fn neighbours(&self, cell: IVec2) -> impl Iterator<Item = (IVec2, u32)> + '_ {
+        NEIGHBOUR_OFFSETS.iter().filter_map(move |offset| {
+            let neighbour = cell + *offset;
+
+            if !self.is_walkable(neighbour) {
+                return None;
+            }
+
+            if offset.x != 0 && offset.y != 0 {
+                let cuts_corner = !self.is_walkable(cell + IVec2::new(offset.x, 0))
+                    || !self.is_walkable(cell + IVec2::new(0, offset.y));
+
+                return (!cuts_corner).then_some((neighbour, DIAGONAL_COST));
+            }
+
+            Some((neighbour, STRAIGHT_COST))
+        })
+    }
+}
+
+/// Distances from every reachable cell around the player to the player, shared by all mobs.
+#[derive(Resource)]
+pub struct FlowField {
+    target: Option<IVec2>,
+    distances: HashMap<IVec2, u32>,
+    /// set when the nav grid changed since the last update
+    stale: bool,
+    timer: Timer,
+}
+
+impl Default for FlowField {
+    fn default() -> Self {
+        FlowField {
+            target: None,
+            distances: HashMap::default(),
+            stale: true,
+            timer: Timer::from_seconds(FLOW_FIELD_INTERVAL, TimerMode::Repeating),
+        }
+    }
+}
+
+impl FlowField {
+    /// Direction to walk from the given position to get closer to the player,
+    /// `None` if the position is not covered or already next to the player.
+    pub fn direction(&self, nav_grid: &NavGrid, position: Vec2) -> Option<Vec2> {
+        let cell = NavGrid::world_to_cell(position);
+        let distance = self.distances.get(&cell).copied().unwrap_or(u32::MAX);
+
+        if distance == 0 {
+            return None;
+        }
+
+        let (next, next_distance) = nav_grid
+            .neighbours(cell)
+            .filter_map(|(neighbour, _)| Some((neighbour, *self.distances.get(&neighbour)?)))
+            .min_by_key(|(_, distance)| *distance)?;
+
+        if next_distance >= distance || next_distance == 0 {
+            return None;
+        }
+
+        Some((NavGrid::cell_to_world(next) - position).normalize_or_zero())
+    }
+
+    fn rebuild(&mut self, nav_grid: &NavGrid, target: IVec2) {
+        self.target = Some(target);
+        self.distances.clear();
+        self.distances.insert(target, 0);
+
+        let mut open = BinaryHeap::from([Reverse((0, target.x, target.y))]);
+
+        while let Some(Reverse((distance, x, y))) = open.pop() {
+            let cell = IVec2::new(x, y);
+
+            if self
+                .distances
+                .get(&cell)
+                .is_some_and(|known| *known < distance)
+            {
+                continue;
+            }
+
+            for (neighbour, cost) in nav_grid.neighbours(cell) {
+                if (neighbour - target).abs().max_element() > FLOW_FIELD_RADIUS {
+                    continue;
+                }
+
+                let neighbour_distance = distance + cost;
+
+                if self
+                    .distances
+                    .get(&neighbour)
+                    .is_some_and(|known| *known <= neighbour_distance)
+                {
+                    continue;
+                }
+
+                self.distances.insert(neighbour, neighbour_distance);
+                open.push(Reverse((neighbour_distance, neighbour.x, neighbour.y)));
+            }
+        }
+    }
+}
+
+/// Rebuilds the nav tiles of newly opened tiles and tiles with newly felled trees.
+pub fn update_nav_grid(
+    grid: Res<TileGrid>,
+    mut nav_grid: ResMut<NavGrid>,
+    seed: Res<WorldSeed>,
+    biome_map: Res<BiomeMap>,
+    balance: Res<GameBalance>,
+) {
+    if !grid.is_changed() {
+        return;
+    }
+
+    let stale_tiles = nav_grid
+        .tiles
+        .keys()
+        .filter(|position| grid.state(**position) != Some(TileState::Open))
+        .copied()
+        .collect_vec();
+
+    for position in stale_tiles {
+        nav_grid.tiles.remove(&position);
+    }
+
+    for (position, tile) in grid.iter() {
+        if tile.state != TileState::Open {
+            continue;
+        }
+
+        let felled_trees = tile.changes.felled_trees.len();
+
+        if nav_grid
+            .tiles
+            .get(&position)
+            .is_some_and(|nav_tile| nav_tile.felled_trees == felled_trees)
+        {
+            continue;
+        }
+
+        let layout = tile_layout(position, &seed, &biome_map, &balance);
+        let mut walkable = vec![true; (SUB_TILES * SUB_TILES) as usize];
+
+        let standing_trees = layout
+            .trees
+            .iter()
+            .map(|(sub_tile, _)| *sub_tile)
+            .filter(|sub_tile| !tile.changes.felled_trees.contains(sub_tile));
+
+        for sub_tile in standing_trees.chain(layout.rocks) {
+            walkable[NavGrid::sub_tile_index(sub_tile)] = false;
+        }
+
+        nav_grid.tiles.insert(
+            position,
+            NavTile {
+                walkable,
+                felled_trees,
+            },
+        );
+    }
+}
+
+/// Blocks the cells under static colliders that aren't trees or rocks of a tile,
+/// rebuilt whenever one of them is added, moved or removed.
+pub fn update_nav_obstacles(
+    mut nav_grid: ResMut<NavGrid>,
+    obstacles: Query<
+        (Ref<Position>, &Rotation, &Collider, &RigidBody),
+        (
+            Without<Sensor>,
+            Without<Tile>,
+            Without<Tree>,
+            Without<Rock>,
+            Without<Player>,
+        ),
+    >,
+    mut removed: RemovedComponents<Collider>,
+) {
+    let removed_any = removed.read().count() > 0;
+
+    if !removed_any && !obstacles.iter().any(|(position, ..)| position.is_changed()) {
+        return;
+    }
+
+    let obstacle_cells = obstacles
+        .iter()
+        .filter(|(.., rigid_body)| rigid_body.is_static())
+        .flat_map(|(position, rotation, collider, _)| {
+            let aabb = collider.aabb(position.0, *rotation);
+
+            NavGrid::covered_cells(aabb.min, aabb.max)
+        })
+        .collect::<HashSet<_>>();
+
+    if obstacle_cells != nav_grid.obstacles {
+        nav_grid.obstacles = obstacle_cells;
+    }
+}
+
+pub fn update_flow_field(
+    time: Res<Time>,
+    nav_grid: Res<NavGrid>,
+    mut flow_field: ResMut<FlowField>,
+    player_query: Query<&Transform, With<Player>>,
+) {
+    flow_field.stale |= nav_grid.is_changed();
+
+    if !flow_field.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+
+    let target = NavGrid::world_to_cell(player_transform.translation.xy());
+
+    if flow_field.target == Some(target) && !flow_field.stale {
+        return;
+    }
+
+    flow_field.rebuild(&nav_grid, target);
+    flow_field.stale = false;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::sub_tile_offset;
+
+    #[test]
+    fn cell_tile_round_trips_through_the_world_position() {
+        for x in -40..40 {
+            for y in -40..40 {
+                let cell = IVec2::new(x, y);
+                let (tile, sub_tile) = NavGrid::cell_tile(cell);
+                let world = NavGrid::cell_to_world(cell);
+
+                assert!(sub_tile.x < SUB_TILES as u32 && sub_tile.y < SUB_TILES as u32);
+                assert_eq!(TileGrid::world_to_grid(world), tile);
+                assert_eq!(
+                    TileGrid::grid_to_world(tile) + sub_tile_offset(sub_tile),
+                    world
+                );
+                assert_eq!(NavGrid::world_to_cell(world), cell);
+            }
+        }
+    }
+
+    #[test]
+    fn center_cell_sits_on_the_tile_position() {
+        let center = UVec2::splat(SUB_TILES as u32 / 2);
+
+        assert_eq!(NavGrid::cell_tile(IVec2::ZERO), (IVec2::ZERO, center));
+        assert_eq!(
+            NavGrid::cell_tile(IVec2::new(SUB_TILES as i32, 0)),
+            (IVec2::new(1, 0), center)
+        );
+    }
+
+    #[test]
+    fn obstacles_block_every_cell_they_overlap() {
+        // the 100x100 wall at 100/100 spans 50..150, cell 3 covers 40..56 and cell 9 136..152
+        let cells = NavGrid::covered_cells(Vec2::splat(50.), Vec2::splat(150.)).collect_vec();
+
+        assert_eq!(cells.len(), 49);
+        assert!(cells.contains(&IVec2::new(3, 3)));
+        assert!(cells.contains(&IVec2::new(9, 9)));
+        assert!(!cells.contains(&IVec2::new(2, 3)));
+        assert!(!cells.contains(&IVec2::new(10, 9)));
+    }
+}
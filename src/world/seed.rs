@@ -0,0 +1,48 @@
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{thread_rng, Rng, SeedableRng};
+use std::env;
+
+pub static SEED_ENV_VAR: &str = "FROZEN_FOREST_SEED";
+
+/// Seed of the whole world. Every tile derives its own rng from it, so the same seed always
+/// produces the same map regardless of the order in which tiles are opened.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WorldSeed(pub u64);
+
+impl WorldSeed {
+    /// Reads the seed from `FROZEN_FOREST_SEED` and falls back to a random one.
+    pub fn from_env() -> Self {
+        let seed = env::var(SEED_ENV_VAR)
+            .ok()
+            .and_then(|value| match value.trim().parse() {
+                Ok(seed) => Some(seed),
+                Err(_) => {
+                    warn!("ignoring invalid {SEED_ENV_VAR} value {value:?}");
+                    None
+                }
+            })
+            .unwrap_or_else(|| thread_rng().gen());
+
+        WorldSeed(seed)
+    }
+
+    /// Rng used to lay out the tile at the given grid position.
+    pub fn tile_rng(&self, position: IVec2) -> StdRng {
+        StdRng::seed_from_u64(self.tile_seed(position))
+    }
+
+    pub fn tile_seed(&self, position: IVec2) -> u64 {
+        let mut hash = split_mix(self.0);
+        hash = split_mix(hash ^ position.x as u64);
+        split_mix(hash ^ position.y as u64)
+    }
+}
+
+// splitmix64 finalizer, spreads neighbouring coordinates over the whole u64 range
+fn split_mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
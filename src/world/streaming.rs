@@ -0,0 +1,70 @@
+use crate::balance::GameBalance;
+use crate::world::biome::BiomeMap;
+use crate::world::grid::{TileGrid, TileState};
+use crate::world::seed::WorldSeed;
+use crate::world::{populate_tile, tile_layout, TreeAssets};
+use bevy::prelude::*;
+
+/// Open tiles within this many tiles of the camera get their content spawned.
+pub static LOAD_DISTANCE: i32 = 3;
+/// Open tiles further away than this get their content removed again.
+/// Larger than `LOAD_DISTANCE` so tiles on the border don't flicker in and out.
+pub static UNLOAD_DISTANCE: i32 = 4;
+
+pub fn stream_tiles(
+    mut commands: Commands,
+    mut grid: ResMut<TileGrid>,
+    camera_query: Query<&Transform, With<Camera>>,
+    seed: Res<WorldSeed>,
+    biome_map: Res<BiomeMap>,
+    balance: Res<GameBalance>,
+    tree_assets: Res<TreeAssets>,
+) {
+    let Ok(camera_transform) = camera_query.get_single() else {
+        return;
+    };
+
+    let center = TileGrid::world_to_grid(camera_transform.translation.xy());
+
+    let mut load = vec![];
+    let mut unload = vec![];
+
+    for (position, tile) in grid.iter() {
+        if tile.state != TileState::Open {
+            continue;
+        }
+
+        let distance = (position - center).abs().max_element();
+
+        if tile.loaded && distance > UNLOAD_DISTANCE {
+            unload.push(position);
+        } else if !tile.loaded && distance <= LOAD_DISTANCE {
+            load.push(position);
+        }
+    }
+
+    for position in unload {
+        let Some(tile) = grid.get_mut(position) else {
+            continue;
+        };
+
+        commands.entity(tile.entity).despawn_descendants();
+        tile.loaded = false;
+    }
+
+    for position in load {
+        let Some(tile) = grid.get_mut(position) else {
+            continue;
+        };
+
+        populate_tile(
+            &mut commands,
+            tile.entity,
+            tile_layout(position, &seed, &biome_map, &balance),
+            &tile.changes,
+            &tree_assets,
+            balance.trees.health,
+        );
+        tile.loaded = true;
+    }
+}
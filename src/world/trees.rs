@@ -0,0 +1,109 @@
+use crate::balance::GameBalance;
+use crate::entities::data::{AttackableFrom, EntityType, Health};
+use crate::entities::event::EntityDeathEvent;
+use crate::ui::Inventory;
+use crate::world::grid::TileGrid;
+use crate::world::{sub_tile_offset, Tile, TREE_SPRITE_SIZE};
+use crate::PhysicsLayers;
+use bevy::math::vec2;
+use bevy::prelude::*;
+use bevy_xpbd_2d::prelude::*;
+use iter_tools::Itertools;
+
+pub static STUMP_SIZE: f32 = 6.;
+
+#[derive(Component)]
+pub struct Tree {
+    pub sub_tile: UVec2,
+}
+
+#[derive(Component)]
+pub struct Stump;
+
+/// Spawns a tree on the given sub tile. The tree entity sits at the trunk so its collider
+/// is the entity melee attacks hit, the sprite is a child above it.
+pub fn spawn_tree(
+    parent: &mut ChildBuilder,
+    sub_tile: UVec2,
+    atlas: TextureAtlas,
+    texture: Handle<Image>,
+    health: f64,
+) {
+    let trunk_position = sub_tile_offset(sub_tile) - vec2(0., TREE_SPRITE_SIZE / 4.);
+
+    parent
+        .spawn((
+            Tree { sub_tile },
+            EntityType::Tree,
+            AttackableFrom(vec![EntityType::Player]),
+            Health(health),
+            SpatialBundle {
+                transform: Transform::from_translation(trunk_position.extend(3.)),
+                ..default()
+            },
+            RigidBody::Static,
+            Collider::circle(TREE_SPRITE_SIZE / 4.),
+            CollisionLayers::new(PhysicsLayers::Tree, LayerMask::ALL),
+            Restitution::new(0.),
+        ))
+        .with_children(|parent| {
+            parent.spawn(SpriteSheetBundle {
+                atlas,
+                texture,
+                sprite: Sprite {
+                    custom_size: Some(vec2(TREE_SPRITE_SIZE, TREE_SPRITE_SIZE)),
+                    ..default()
+                },
+                transform: Transform::from_translation(Vec3::new(0., TREE_SPRITE_SIZE / 4., 0.)),
+                ..default()
+            });
+        });
+}
+
+pub fn spawn_stump(parent: &mut ChildBuilder, sub_tile: UVec2) {
+    let trunk_position = sub_tile_offset(sub_tile) - vec2(0., TREE_SPRITE_SIZE / 4.);
+
+    parent.spawn((
+        Stump,
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgb(0.45, 0.3, 0.15),
+                custom_size: Some(vec2(STUMP_SIZE, STUMP_SIZE)),
+                ..default()
+            },
+            transform: Transform::from_translation(trunk_position.extend(3.)),
+            ..default()
+        },
+    ));
+}
+
+/// Turns trees that died this frame into stumps, remembers them on their tile and hands out wood.
+pub fn fell_trees(
+    mut commands: Commands,
+    mut death_events: EventReader<EntityDeathEvent>,
+    trees: Query<(&Tree, &Parent)>,
+    tiles: Query<&Tile>,
+    mut grid: ResMut<TileGrid>,
+    mut inventory: ResMut<Inventory>,
+    balance: Res<GameBalance>,
+) {
+    for dead_entity in death_events.read().map(|event| event.entity).unique() {
+        let Ok((tree, parent)) = trees.get(dead_entity) else {
+            continue;
+        };
+
+        let Ok(tile) = tiles.get(parent.get()) else {
+            continue;
+        };
+
+        if let Some(grid_tile) = grid.get_mut(tile.position) {
+            grid_tile.changes.felled_trees.push(tree.sub_tile);
+        }
+
+        commands
+            .entity(parent.get())
+            .with_children(|parent| spawn_stump(parent, tree.sub_tile));
+
+        inventory.wood += balance.trees.wood;
+    }
+}
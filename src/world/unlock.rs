@@ -0,0 +1,233 @@
+use crate::balance::{GameBalance, UnlockBalance};
+use crate::entities::data::{Mob, Player};
+use crate::entities::event::EntityDeathEvent;
+use crate::world::grid::{TileGrid, TileState};
+use crate::world::TILE_SIZE;
+use bevy::prelude::*;
+use iter_tools::Itertools;
+
+/// Starts at `UnlockBalance::starting_currency` with every run, see `start_world`.
+#[derive(Resource, Default)]
+pub struct Currency(pub u32);
+
+/// Rules a closed tile has to satisfy before it can be opened.
+#[derive(Resource)]
+pub struct UnlockRules {
+    /// the tile has to share an edge with an already open tile,
+    /// the distance to the player is limited by `UnlockBalance::max_player_distance`
+    pub require_adjacent_open_tile: bool,
+}
+
+impl Default for UnlockRules {
+    fn default() -> UnlockRules {
+        UnlockRules {
+            require_adjacent_open_tile: true,
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum ActivationRejection {
+    NotAdjacent,
+    TooFarFromPlayer { distance: f32, max_distance: f32 },
+    NotEnoughCurrency { cost: u32, available: u32 },
+}
+
+/// Sent instead of an `ActivateTileEvent` when the player is not allowed to open the tile.
+#[derive(Event)]
+pub struct TileActivationRejectedEvent {
+    pub tile: Entity,
+    pub reason: ActivationRejection,
+}
+
+#[derive(Component)]
+pub struct UnlockCostLabel;
+
+/// Cost to open the tile at the given grid position, grows with the distance from the origin.
+pub fn unlock_cost(position: IVec2, balance: &UnlockBalance) -> u32 {
+    let distance = position.x.unsigned_abs().max(position.y.unsigned_abs());
+    let rings = distance.saturating_sub(1);
+
+    balance.base_cost + balance.distance_cost * rings * rings
+}
+
+/// Returns the cost of opening the tile or the reason why it can't be opened.
+pub fn check_unlock(
+    position: IVec2,
+    grid: &TileGrid,
+    rules: &UnlockRules,
+    balance: &UnlockBalance,
+    player_position: Option<Vec2>,
+    currency: &Currency,
+) -> Result<u32, ActivationRejection> {
+    if rules.require_adjacent_open_tile
+        && !TileGrid::edge_neighbours(position)
+            .any(|neighbour| grid.state(neighbour) == Some(TileState::Open))
+    {
+        return Err(ActivationRejection::NotAdjacent);
+    }
+
+    if let Some(player_position) = player_position {
+        let tile_center = TileGrid::grid_to_world(position);
+        let closest_point =
+            player_position.clamp(tile_center - TILE_SIZE / 2., tile_center + TILE_SIZE / 2.);
+        let distance = player_position.distance(closest_point);
+
+        if distance > balance.max_player_distance {
+            return Err(ActivationRejection::TooFarFromPlayer {
+                distance,
+                max_distance: balance.max_player_distance,
+            });
+        }
+    }
+
+    let cost = unlock_cost(position, balance);
+
+    if currency.0 < cost {
+        return Err(ActivationRejection::NotEnoughCurrency {
+            cost,
+            available: currency.0,
+        });
+    }
+
+    Ok(cost)
+}
+
+/// Only kills credited to the player pay out, not mobs dying to something else.
+pub fn reward_mob_kills(
+    mut death_events: EventReader<EntityDeathEvent>,
+    mobs: Query<(), With<Mob>>,
+    players: Query<(), With<Player>>,
+    mut currency: ResMut<Currency>,
+    balance: Res<GameBalance>,
+) {
+    for event in death_events.read().unique_by(|event| event.entity) {
+        let by_player = event
+            .attacker
+            .is_some_and(|attacker| players.contains(attacker));
+
+        if by_player && mobs.contains(event.entity) {
+            currency.0 += balance.unlock.mob_kill_reward;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn balance() -> UnlockBalance {
+        UnlockBalance {
+            starting_currency: 10,
+            mob_kill_reward: 2,
+            base_cost: 5,
+            distance_cost: 5,
+            max_player_distance: 64.,
+        }
+    }
+
+    fn grid_with_open_origin() -> TileGrid {
+        let mut grid = TileGrid::default();
+        grid.insert(IVec2::ZERO, Entity::from_raw(0), TileState::Open);
+        grid
+    }
+
+    #[test]
+    fn unlock_cost_grows_with_the_rings_around_the_start() {
+        let balance = balance();
+
+        assert_eq!(unlock_cost(IVec2::ZERO, &balance), 5);
+        assert_eq!(unlock_cost(IVec2::new(1, -1), &balance), 5);
+        assert_eq!(unlock_cost(IVec2::new(2, 0), &balance), 10);
+        assert_eq!(unlock_cost(IVec2::new(-3, 1), &balance), 25);
+    }
+
+    #[test]
+    fn check_unlock_returns_the_cost() {
+        let grid = grid_with_open_origin();
+        let result = check_unlock(
+            IVec2::new(1, 0),
+            &grid,
+            &UnlockRules::default(),
+            &balance(),
+            // standing on the shared edge
+            Some(Vec2::new(TILE_SIZE / 2., 0.)),
+            &Currency(10),
+        );
+
+        assert_eq!(result, Ok(5));
+    }
+
+    #[test]
+    fn check_unlock_needs_an_open_edge_neighbour() {
+        let grid = grid_with_open_origin();
+        let rules = UnlockRules::default();
+
+        // diagonal neighbours don't count
+        let result = check_unlock(
+            IVec2::new(1, 1),
+            &grid,
+            &rules,
+            &balance(),
+            None,
+            &Currency(10),
+        );
+
+        assert_eq!(result, Err(ActivationRejection::NotAdjacent));
+
+        let rules = UnlockRules {
+            require_adjacent_open_tile: false,
+        };
+        let result = check_unlock(
+            IVec2::new(1, 1),
+            &grid,
+            &rules,
+            &balance(),
+            None,
+            &Currency(10),
+        );
+
+        assert_eq!(result, Ok(5));
+    }
+
+    #[test]
+    fn check_unlock_needs_a_nearby_player() {
+        let grid = grid_with_open_origin();
+        let player_position = TileGrid::grid_to_world(IVec2::new(-2, 0));
+
+        let result = check_unlock(
+            IVec2::new(1, 0),
+            &grid,
+            &UnlockRules::default(),
+            &balance(),
+            Some(player_position),
+            &Currency(10),
+        );
+
+        assert!(matches!(
+            result,
+            Err(ActivationRejection::TooFarFromPlayer { .. })
+        ));
+    }
+
+    #[test]
+    fn check_unlock_needs_enough_currency() {
+        let grid = grid_with_open_origin();
+        let result = check_unlock(
+            IVec2::new(1, 0),
+            &grid,
+            &UnlockRules::default(),
+            &balance(),
+            None,
+            &Currency(4),
+        );
+
+        assert_eq!(
+            result,
+            Err(ActivationRejection::NotEnoughCurrency {
+                cost: 5,
+                available: 4,
+            })
+        );
+    }
+}